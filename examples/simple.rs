@@ -2,14 +2,14 @@ use std::io;
 
 use pronouner::*;
 
-const CAST: &'static str = include_str!("assets/characters.json");
-const DICT: &'static str = include_str!("assets/dictionary.json");
-const CONVERSATION: &'static str = include_str!("assets/conversation.xyr");
+const CAST: &str = include_str!("assets/characters.json");
+const DICT: &str = include_str!("assets/dictionary.json");
+const CONVERSATION: &str = include_str!("assets/conversation.xyr");
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Let's load the already known cast from the characters file and the verb dictionary from dictionary.json.
-    let mut cast: CharacterCast = serde_json::from_str(CAST)?;
-    let dict = serde_json::from_str(DICT)?;
+    let mut cast = CharacterCast::from_json(CAST)?;
+    let dict = Dictionary::from_json(DICT)?;
 
     // Ask the user their name and pronouns.
     let (name, pronouns) = ask_name_and_pronouns()?;
@@ -62,7 +62,7 @@ fn ask_name_and_pronouns() -> Result<(String, Pronouns), Box<dyn std::error::Err
             continue;
         };
 
-        if value < 1 || value > 4 {
+        if !(1..=4).contains(&value) {
             println!("Please answer with 1, 2, 3, or 4.");
             continue;
         }
@@ -0,0 +1,126 @@
+use proc_macro2::{Literal, TokenStream};
+use pronouner::{DialogMacro, DialogMacroMod, DialogMacroType, Segment, Template};
+use quote::{format_ident, quote};
+use serde_json::Value;
+
+/// The expression that builds the template, for generated code and the `xyr!` macro.
+pub fn template_tokens(template: &Template) -> TokenStream {
+    let segments = template.segments().iter().map(|segment| match segment {
+        Segment::Text(text) => quote!(::pronouner::Segment::Text(#text)),
+        Segment::Macro { macr, source } => {
+            let macr = macro_tokens(macr);
+            quote!(::pronouner::Segment::Macro { macr: #macr, source: #source })
+        }
+    });
+
+    quote!(::pronouner::Template::new(::std::vec![#(#segments),*]))
+}
+
+/// The expression that builds the macro, using the names of its serde representation.
+fn macro_tokens(macr: &DialogMacro) -> TokenStream {
+    let value = serde_json::to_value(macr).unwrap_or_default();
+
+    let character_id = option_str(&value["character_id"]);
+    let _type = macro_type(&value["type"]);
+    let data = option_str(&value["data"]);
+    let mods = value["mods"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(macro_mod);
+
+    let mut tokens = quote!(::pronouner::DialogMacro::new(
+        #character_id,
+        #_type,
+        #data,
+        ::std::vec![#(#mods),*],
+    ));
+
+    if let Some(count) = value.get("count").and_then(Value::as_str) {
+        tokens.extend(quote!(.with_count(#count)));
+    }
+
+    for (key, ty, method) in [
+        ("case", quote!(::pronouner::Case), quote!(with_case)),
+        (
+            "noun_gender",
+            quote!(::pronouner::Gender),
+            quote!(with_noun_gender),
+        ),
+        (
+            "declension",
+            quote!(::pronouner::Declension),
+            quote!(with_declension),
+        ),
+    ] {
+        if let Some(variant) = value.get(key).and_then(Value::as_str) {
+            let variant = format_ident!("{variant}");
+            tokens.extend(quote!(.#method(#ty::#variant)));
+        }
+    }
+
+    tokens
+}
+
+fn option_str(value: &Value) -> TokenStream {
+    match value.as_str() {
+        Some(value) => quote!(::std::option::Option::Some(#value)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn macro_type(value: &Value) -> TokenStream {
+    let name = value.as_str().unwrap_or_default();
+
+    match serde_json::from_value(value.clone()) {
+        Ok(DialogMacroType::Custom(_)) | Err(_) => {
+            quote!(::pronouner::DialogMacroType::Custom(::std::string::String::from(#name)))
+        }
+        Ok(_) => {
+            let variant = format_ident!("{name}");
+            quote!(::pronouner::DialogMacroType::#variant)
+        }
+    }
+}
+
+fn macro_mod(value: &Value) -> TokenStream {
+    // Mods with an argument, like `{"Truncate": 3}`
+    if let Some((name, argument)) = value.as_object().and_then(|object| object.iter().next()) {
+        let variant = format_ident!("{name}");
+        let argument = Literal::usize_unsuffixed(argument.as_u64().unwrap_or_default() as usize);
+        return quote!(::pronouner::DialogMacroMod::#variant(#argument));
+    }
+
+    let name = value.as_str().unwrap_or_default();
+
+    match serde_json::from_value(value.clone()) {
+        Ok(DialogMacroMod::Custom(_)) | Err(_) => {
+            quote!(::pronouner::DialogMacroMod::Custom(::std::string::String::from(#name)))
+        }
+        Ok(_) => {
+            let variant = format_ident!("{name}");
+            quote!(::pronouner::DialogMacroMod::#variant)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_tokens_test() -> Result<(), pronouner::Error> {
+        let template = Template::parse(
+            r#"{{Hi}} {"character_id":"pidge","type_":"Name","mod":["Capitalized",{"Truncate":3},"Pirate"],"case":"Dative"}"#,
+        )?;
+        let tokens = template_tokens(&template).to_string();
+
+        assert!(tokens.contains(r#":: pronouner :: Segment :: Text ("{")"#));
+        assert!(tokens.contains(":: pronouner :: DialogMacroType :: Name"));
+        assert!(tokens.contains(":: pronouner :: DialogMacroMod :: Truncate (3)"));
+        assert!(tokens.contains(r#"Custom (:: std :: string :: String :: from ("Pirate"))"#));
+        assert!(tokens.contains(". with_case (:: pronouner :: Case :: Dative)"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,350 @@
+//! Precompiles XYR dialog in build scripts.
+//!
+//! `Precompiler` parses every `.xyr` file of a directory, checks the dialog against the cast and
+//! dictionary and generates Rust code that builds the parsed templates, so shipping builds never
+//! parse the JSON of their macros at runtime:
+//!
+//! ```no_run
+//! // In build.rs
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("dialog.rs");
+//!
+//! pronouner_build::Precompiler::new("assets/dialog")
+//!     .with_cast("assets/characters.json")
+//!     .with_dictionary("assets/dictionary.json")
+//!     .with_character("player")
+//!     .write(out)
+//!     .unwrap();
+//! ```
+//!
+//! The generated code is included in the game with
+//! `include!(concat!(env!("OUT_DIR"), "/dialog.rs"));`.
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use pronouner::{CharacterCast, Dictionary, Template, TextId, XyrDocument};
+use quote::quote;
+
+mod codegen;
+mod validator;
+
+pub use codegen::template_tokens;
+pub use validator::Validator;
+
+const EXTENSION: &str = "xyr";
+
+#[derive(Debug)]
+pub enum Error {
+    /// A file couldn't be read or written.
+    Io(PathBuf, std::io::Error),
+    /// A cast, dictionary or dialog file is malformed.
+    File(PathBuf, pronouner::Error),
+    /// A line or choice of a dialog file is malformed or uses unknown names.
+    Text {
+        path: PathBuf,
+        id: String,
+        error: pronouner::Error,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::File(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::Text { path, id, error } => write!(f, "{} {id}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Generates the templates of all `.xyr` files in a directory and its subdirectories.
+///
+/// Templates are keyed by the path of their file relative to the directory, without the
+/// extension, and the `TextId` of the line or choice, e.g. `act1/tavern/start.3`. The generated
+/// code defines two functions that build the templates:
+///
+/// - `templates()`, for the text of every dialog line and choice.
+/// - `speakers()`, for the speaker templates of the lines that have one.
+pub struct Precompiler {
+    dir: PathBuf,
+    cast: Option<PathBuf>,
+    dictionary: Option<PathBuf>,
+    types: Vec<String>,
+    mods: Vec<String>,
+    characters: Vec<String>,
+}
+
+impl Precompiler {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            cast: None,
+            dictionary: None,
+            types: Vec::new(),
+            mods: Vec::new(),
+            characters: Vec::new(),
+        }
+    }
+
+    /// Checks character identifiers against a cast file.
+    pub fn with_cast(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cast = Some(path.into());
+        self
+    }
+
+    /// Checks verb keys against a dictionary file.
+    pub fn with_dictionary(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dictionary = Some(path.into());
+        self
+    }
+
+    pub fn with_macro_type(mut self, name: impl Into<String>) -> Self {
+        self.types.push(name.into());
+        self
+    }
+
+    pub fn with_mod(mut self, name: impl Into<String>) -> Self {
+        self.mods.push(name.into());
+        self
+    }
+
+    /// Accepts a character that isn't in the cast file, e.g. the player.
+    pub fn with_character(mut self, id: impl Into<String>) -> Self {
+        self.characters.push(id.into());
+        self
+    }
+
+    /// The Rust code that builds the templates.
+    pub fn generate(&self) -> Result<String, Error> {
+        let cast = self.cast.as_deref().map(read_cast).transpose()?;
+
+        let dictionary_src = self
+            .dictionary
+            .as_deref()
+            .map(read_dictionary)
+            .transpose()?;
+        let dictionary = match (&self.dictionary, &dictionary_src) {
+            (Some(path), Some(src)) => {
+                Some(Dictionary::from_json(src).map_err(|e| Error::File(path.clone(), e))?)
+            }
+            _ => None,
+        };
+
+        let mut validator = Validator::new(cast, dictionary);
+
+        for name in &self.types {
+            validator = validator.with_macro_type(name.clone());
+        }
+
+        for name in &self.mods {
+            validator = validator.with_mod(name.clone());
+        }
+
+        for id in &self.characters {
+            validator = validator.with_character(id.clone());
+        }
+
+        let mut files = Vec::new();
+        find_files(&self.dir, &mut files)?;
+
+        let mut texts = Vec::new();
+        let mut speakers = Vec::new();
+
+        for path in files {
+            let src = read(&path)?;
+            let document = XyrDocument::parse(&src).map_err(|e| Error::File(path.clone(), e))?;
+            let name = self.name(&path);
+
+            let compile = |id: &TextId, text: &str| {
+                let template = Template::parse(text)
+                    .and_then(|template| validator.check(&template).map(|()| template))
+                    .map_err(|error| Error::Text {
+                        path: path.clone(),
+                        id: id.to_string(),
+                        error,
+                    })?;
+
+                let key = format!("{name}/{id}");
+                let template = template_tokens(&template);
+                Ok::<_, Error>(quote!((#key, #template)))
+            };
+
+            for (id, text) in document.texts() {
+                texts.push(compile(&id, text)?);
+            }
+
+            for node in document.nodes() {
+                for (index, line) in node.lines().iter().enumerate() {
+                    if let Some(speaker) = line.speaker() {
+                        let id = TextId::Line {
+                            node: node.label().to_string(),
+                            index,
+                        };
+                        speakers.push(compile(&id, speaker)?);
+                    }
+                }
+            }
+        }
+
+        let code = quote! {
+            /// The templates of every dialog line and choice, keyed by file and text ID.
+            pub fn templates(
+            ) -> ::std::collections::HashMap<&'static str, ::pronouner::Template<'static>> {
+                ::std::collections::HashMap::from([#(#texts),*])
+            }
+
+            /// The speaker templates of the dialog lines that have one, keyed by file and text ID.
+            pub fn speakers(
+            ) -> ::std::collections::HashMap<&'static str, ::pronouner::Template<'static>> {
+                ::std::collections::HashMap::from([#(#speakers),*])
+            }
+        };
+
+        Ok(format!(
+            "// Generated by pronouner-build from {}, don't edit.\n\n{code}\n",
+            self.dir.display()
+        ))
+    }
+
+    /// Writes the generated code to a file and tells Cargo to rerun the build script when the
+    /// dialog, cast or dictionary changes.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let code = self.generate()?;
+
+        std::fs::write(path.as_ref(), code).map_err(|e| Error::Io(path.as_ref().into(), e))?;
+
+        for path in [
+            Some(&self.dir),
+            self.cast.as_ref(),
+            self.dictionary.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+
+        Ok(())
+    }
+
+    /// The path of a file relative to the directory, without its extension.
+    fn name(&self, path: &Path) -> String {
+        let path = path.strip_prefix(&self.dir).unwrap_or(path);
+
+        path.with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Reads a cast file of any version.
+pub fn read_cast(path: &Path) -> Result<CharacterCast, Error> {
+    CharacterCast::from_json(&read(path)?).map_err(|e| Error::File(path.into(), e))
+}
+
+/// Reads the source of a dictionary file, for `Dictionary::from_json` to borrow from.
+pub fn read_dictionary(path: &Path) -> Result<String, Error> {
+    read(path)
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|e| Error::Io(path.into(), e))
+}
+
+/// Collects the dialog files of a directory and its subdirectories, sorted so the generated code
+/// doesn't change between builds.
+fn find_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let entries = std::fs::read_dir(dir).map_err(|e| Error::Io(dir.into(), e))?;
+
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::Io(dir.into(), e))?;
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            find_files(&path, files)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSETS: &str = "../examples/assets";
+
+    fn gen_precompiler() -> Precompiler {
+        Precompiler::new(ASSETS)
+            .with_cast(Path::new(ASSETS).join("characters.json"))
+            .with_dictionary(Path::new(ASSETS).join("dictionary.json"))
+    }
+
+    #[test]
+    fn generate_test() -> Result<(), Error> {
+        let code = gen_precompiler().with_character("player").generate()?;
+
+        assert!(code.starts_with("// Generated by pronouner-build"));
+        assert!(syn::parse_file(&code).is_ok());
+        assert!(code.contains(r#"("conversation/start.0" , :: pronouner :: Template :: new"#));
+        assert!(code.contains(r#"Some ("player") , :: pronouner :: DialogMacroType :: Name"#));
+        assert!(!code.contains("serde_json"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn errors_test() {
+        let error = gen_precompiler().generate().unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::Text {
+                id,
+                error: pronouner::Error::UnknownCharacterIdentifier(_),
+                ..
+            } if id == "start.1"
+        ));
+        assert!(error
+            .to_string()
+            .ends_with("conversation.xyr start.1: unknown character identifier `player`"));
+
+        assert!(matches!(
+            Precompiler::new("missing").generate(),
+            Err(Error::Io(..))
+        ));
+        assert!(matches!(
+            gen_precompiler()
+                .with_cast(Path::new(ASSETS).join("dictionary.json"))
+                .generate(),
+            Err(Error::File(..))
+        ));
+    }
+
+    #[test]
+    fn name_test() {
+        let precompiler = Precompiler::new("assets/dialog");
+
+        assert_eq!(
+            precompiler.name(Path::new("assets/dialog/act1/tavern.xyr")),
+            "act1/tavern"
+        );
+        assert_eq!(
+            precompiler.name(Path::new("assets/dialog/intro.xyr")),
+            "intro"
+        );
+    }
+}
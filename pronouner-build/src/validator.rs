@@ -0,0 +1,123 @@
+use pronouner::{
+    CharacterCast, DialogMacroCompiler, Dictionary, Error, GrammaticalCharacter, RenderContext,
+    Template,
+};
+
+/// Checks templates for unknown macro types, mods, characters and verbs without rendering them.
+///
+/// Characters and verbs are only checked when a cast or dictionary is given. Characters that are
+/// added to the cast at runtime, like the player, can be listed with `with_character`.
+pub struct Validator<'a> {
+    compiler: DialogMacroCompiler<'a>,
+    check_characters: bool,
+    check_verbs: bool,
+    characters: Vec<String>,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(cast: Option<CharacterCast>, dictionary: Option<Dictionary<'a>>) -> Self {
+        Self {
+            check_characters: cast.is_some(),
+            check_verbs: dictionary.is_some(),
+            compiler: DialogMacroCompiler::new(
+                cast.unwrap_or_default(),
+                dictionary.unwrap_or_default(),
+            ),
+            characters: Vec::new(),
+        }
+    }
+
+    pub fn with_macro_type(mut self, name: impl Into<String>) -> Self {
+        self.compiler =
+            self.compiler.with_macro(
+                name,
+                |_: Option<&GrammaticalCharacter>, _: Option<&str>, _: &RenderContext| {
+                    Ok(String::new())
+                },
+            );
+        self
+    }
+
+    pub fn with_mod(mut self, name: impl Into<String>) -> Self {
+        self.compiler = self.compiler.with_mod(name, |input: String| input);
+        self
+    }
+
+    pub fn with_character(mut self, id: impl Into<String>) -> Self {
+        self.characters.push(id.into());
+        self
+    }
+
+    /// Checks every macro of the template, failing on the first unknown name. Characters and
+    /// verbs are checked independently, so an allowed character still has its verb checked.
+    pub fn check(&self, template: &Template) -> Result<(), Error> {
+        for macr in template.macros() {
+            self.compiler.check_names(macr)?;
+
+            match self.compiler.check_character(macr) {
+                Err(Error::UnknownCharacterIdentifier(unknown))
+                    if !self.check_characters
+                        || self.characters.iter().any(|id| id == unknown.name()) => {}
+                result => result?,
+            }
+
+            if self.check_verbs {
+                self.compiler.check_verb(macr)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Res = Result<(), Error>;
+
+    #[test]
+    fn check_test() -> Res {
+        let cast = CharacterCast::from_json(include_str!("../../examples/assets/characters.json"))?;
+        let template = Template::parse(
+            r#"{"character_id":"player","type":"Species","mods":["Pirate"]} {"character_id":"pidge","type":"VerbConjugate","data":"to bee"}"#,
+        )?;
+
+        let validator = Validator::new(None, None);
+        assert!(matches!(
+            validator.check(&template),
+            Err(Error::UnknownMacroType(_))
+        ));
+
+        let validator = validator.with_macro_type("Species").with_mod("Pirate");
+        validator.check(&template)?;
+
+        let validator = Validator::new(Some(cast), Some(Dictionary::default()))
+            .with_macro_type("Species")
+            .with_mod("Pirate");
+        assert!(matches!(
+            validator.check(&template),
+            Err(Error::UnknownCharacterIdentifier(_))
+        ));
+
+        let validator = validator.with_character("player");
+        assert!(matches!(
+            validator.check(&template),
+            Err(Error::UnknownVerbKey(_))
+        ));
+
+        // An allowed character still has its verb checked
+        let cast = CharacterCast::from_json(include_str!("../../examples/assets/characters.json"))?;
+        let dictionary =
+            Dictionary::from_json(include_str!("../../examples/assets/dictionary.json"))?;
+        let template =
+            Template::parse(r#"{"character_id":"player","type":"VerbConjugate","data":"to bee"}"#)?;
+        let validator = Validator::new(Some(cast), Some(dictionary)).with_character("player");
+        assert!(matches!(
+            validator.check(&template),
+            Err(Error::UnknownVerbKey(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! The `xyr!` macro, which parses XYR dialog at compile time.
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use pronouner::{Dictionary, Template};
+use pronouner_build::{read_cast, read_dictionary, template_tokens, Validator};
+use quote::quote;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Ident, LitStr, Token,
+};
+
+/// Parses an XYR literal at compile time and expands to a `pronouner::Template`.
+///
+/// Malformed macros, unknown keys and unknown macro types and mods are compile errors. Custom
+/// macro types and mods have to be listed to be accepted. Given a cast or dictionary file,
+/// relative to the manifest of the crate, character identifiers and verb keys are checked too:
+///
+/// ```ignore
+/// let greeting = xyr!(
+///     r#"Hey, {"character_id":"pidge","type":"Name"}!"#,
+///     cast = "assets/characters.json",
+///     dictionary = "assets/dictionary.json",
+///     types = ["Faction"],
+///     mods = ["Pirate"],
+/// );
+///
+/// let text = compiler.compile_template(&greeting)?;
+/// ```
+#[proc_macro]
+pub fn xyr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    syn::parse_macro_input!(input as XyrInput)
+        .expand()
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct XyrInput {
+    src: LitStr,
+    cast: Option<LitStr>,
+    dictionary: Option<LitStr>,
+    types: Vec<String>,
+    mods: Vec<String>,
+}
+
+impl Parse for XyrInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut xyr = Self {
+            src: input.parse()?,
+            cast: None,
+            dictionary: None,
+            types: Vec::new(),
+            mods: Vec::new(),
+        };
+
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "cast" => xyr.cast = Some(input.parse()?),
+                "dictionary" => xyr.dictionary = Some(input.parse()?),
+                "types" => xyr.types = parse_names(input)?,
+                "mods" => xyr.mods = parse_names(input)?,
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "expected `cast`, `dictionary`, `types` or `mods`",
+                    ))
+                }
+            }
+        }
+
+        Ok(xyr)
+    }
+}
+
+fn parse_names(input: ParseStream) -> syn::Result<Vec<String>> {
+    let content;
+    bracketed!(content in input);
+
+    let names = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+    Ok(names.iter().map(LitStr::value).collect())
+}
+
+impl XyrInput {
+    fn expand(&self) -> syn::Result<TokenStream> {
+        let src = self.src.value();
+        let error = |error: &dyn std::fmt::Display| syn::Error::new(self.src.span(), error);
+
+        let template = Template::parse(&src).map_err(|e| error(&e))?;
+
+        let cast_path = self.cast.as_ref().map(asset_path);
+        let cast = match (&self.cast, &cast_path) {
+            (Some(lit), Some(path)) => {
+                Some(read_cast(path).map_err(|e| syn::Error::new(lit.span(), e))?)
+            }
+            _ => None,
+        };
+
+        let dictionary_path = self.dictionary.as_ref().map(asset_path);
+        let dictionary_src = match (&self.dictionary, &dictionary_path) {
+            (Some(lit), Some(path)) => {
+                Some(read_dictionary(path).map_err(|e| syn::Error::new(lit.span(), e))?)
+            }
+            _ => None,
+        };
+        let dictionary = match (&self.dictionary, &dictionary_src) {
+            (Some(lit), Some(src)) => {
+                Some(Dictionary::from_json(src).map_err(|e| syn::Error::new(lit.span(), e))?)
+            }
+            _ => None,
+        };
+
+        let mut validator = Validator::new(cast, dictionary);
+
+        for name in &self.types {
+            validator = validator.with_macro_type(name.clone());
+        }
+
+        for name in &self.mods {
+            validator = validator.with_mod(name.clone());
+        }
+
+        validator.check(&template).map_err(|e| error(&e))?;
+
+        // Rebuild the template when the files change
+        let assets = [cast_path, dictionary_path]
+            .into_iter()
+            .flatten()
+            .map(|path| {
+                let path = path.to_string_lossy();
+                quote!(
+                    const _: &[u8] = ::std::include_bytes!(#path);
+                )
+            });
+
+        let template = template_tokens(&template);
+
+        Ok(quote!({
+            #(#assets)*
+            #template
+        }))
+    }
+}
+
+/// Assets are relative to the manifest of the crate that uses the macro.
+fn asset_path(path: &LitStr) -> PathBuf {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    Path::new(&manifest_dir).join(path.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: TokenStream) -> Result<String, String> {
+        syn::parse2::<XyrInput>(input)
+            .and_then(|input| input.expand())
+            .map(|tokens| tokens.to_string())
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn errors_test() {
+        assert_eq!(
+            expand(quote!(r#"{"character_id":"pidge","typ":"Name"}"#)).unwrap_err(),
+            "unknown macro key `typ`, did you mean `type`? at line 1 column 29"
+        );
+        assert_eq!(
+            expand(quote!(r#"{"character_id":"pidge","type":"Nmae"}"#)).unwrap_err(),
+            "unknown macro type `Nmae`, did you mean `Name`?"
+        );
+        assert_eq!(
+            expand(quote!(r#"{"type":"Name","mods":["Pirate"]}"#)).unwrap_err(),
+            "unknown macro mod `Pirate`"
+        );
+        assert_eq!(
+            expand(quote!("Hi}")).unwrap_err(),
+            "unmatched closing brace"
+        );
+
+        // Characters and verbs are only checked against files
+        let verb = quote!(r#"{"character_id":"pigde","type":"VerbConjugate","data":"to bee"}"#);
+        assert!(expand(verb.clone()).is_ok());
+        assert_eq!(
+            expand(quote!(#verb, cast = "../examples/assets/characters.json")).unwrap_err(),
+            "unknown character identifier `pigde`, did you mean `pidge`?"
+        );
+        assert_eq!(
+            expand(quote!(
+                r#"{"character_id":"pidge","type":"VerbConjugate","data":"to bee"}"#,
+                cast = "../examples/assets/characters.json",
+                dictionary = "../examples/assets/dictionary.json",
+            ))
+            .unwrap_err(),
+            "unknown verb key `to bee`, did you mean `to be`?"
+        );
+        assert_eq!(
+            expand(quote!(#verb, dictionary = "../examples/assets/dictionary.json")).unwrap_err(),
+            "unknown verb key `to bee`, did you mean `to be`?"
+        );
+        assert!(expand(quote!("Hi", cast = "missing.json"))
+            .unwrap_err()
+            .contains("missing.json"));
+    }
+}
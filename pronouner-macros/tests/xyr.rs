@@ -0,0 +1,25 @@
+use pronouner::{CharacterCast, DialogMacroCompiler, Dictionary, Error};
+use pronouner_macros::xyr;
+
+#[test]
+fn xyr_test() -> Result<(), Error> {
+    let cast = CharacterCast::from_json(include_str!("../../examples/assets/characters.json"))?;
+    let dictionary = Dictionary::from_json(include_str!("../../examples/assets/dictionary.json"))?;
+    let compiler = DialogMacroCompiler::new(cast, dictionary)
+        .with_mod("Shout", |input: String| input.to_uppercase());
+
+    let template = xyr!(
+        r#"{"character_id":"tupo","type":"SubjectivePronoun","mods":["Capitalized"]} {"character_id":"tupo","type":"VerbConjugate","data":"to be"} {"character_id":"hunk","type":"Name","mods":["Shout",{"Truncate":2}]}'s {{friend}}."#,
+        cast = "../examples/assets/characters.json",
+        dictionary = "../examples/assets/dictionary.json",
+        mods = ["Shout"],
+    );
+
+    assert_eq!(template.macros().count(), 3);
+    assert_eq!(
+        compiler.compile_template(&template)?,
+        "Xe is HU's {friend}."
+    );
+
+    Ok(())
+}
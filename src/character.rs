@@ -1,15 +1,65 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fmt::Display};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::verbs::ConjugatePerson;
+use crate::{
+    format,
+    grammar::{English, Gender, Grammar, Inflection, PronounForm},
+    verbs::ConjugatePerson,
+    Error,
+};
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// The characters of a game. Casts of any format version can be deserialized, also when they are
+/// part of other data like a save file, because older layouts are upgraded first.
+#[derive(Debug, Default, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CharacterCast {
+    #[serde(rename = "characters")]
     map: HashMap<String, GrammaticalCharacter>,
+    /// Named groups of characters that are treated as one plural subject.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    groups: HashMap<String, Vec<String>>,
+    /// Overrides of character data, keyed by locale and then by character identifier.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    localizations: HashMap<String, HashMap<String, CharacterLocalization>>,
+}
+
+impl<'de> Deserialize<'de> for CharacterCast {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// The current layout, which also reads the `"map"` of version 0.
+        #[derive(Deserialize)]
+        struct Layout {
+            #[serde(default, rename = "version")]
+            _version: format::Version,
+            #[serde(alias = "map")]
+            characters: HashMap<String, GrammaticalCharacter>,
+            #[serde(default)]
+            groups: HashMap<String, Vec<String>>,
+            #[serde(default)]
+            localizations: HashMap<String, HashMap<String, CharacterLocalization>>,
+        }
+
+        let layout = Layout::deserialize(deserializer)?;
+
+        Ok(Self {
+            map: layout.characters,
+            groups: layout.groups,
+            localizations: layout.localizations,
+        })
+    }
 }
 
 impl CharacterCast {
+    /// Reads a cast file of any format version, upgrading older layouts.
+    pub fn from_json(src: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(src)?)
+    }
+
+    /// Writes the cast in the current format version.
+    pub fn to_json(&self) -> Result<String, Error> {
+        format::to_json(self)
+    }
+
     pub fn get(&self, key: &str) -> Option<&GrammaticalCharacter> {
         self.map.get(key)
     }
@@ -25,9 +75,137 @@ impl CharacterCast {
     pub fn remove(&mut self, key: &str) -> Option<GrammaticalCharacter> {
         self.map.remove(key)
     }
+
+    /// The identifiers of all characters.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    /// The identifiers of all groups.
+    pub fn group_keys(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    /// The character identifiers of the members of a group.
+    pub fn group(&self, key: &str) -> Option<&[String]> {
+        self.groups.get(key).map(Vec::as_slice)
+    }
+
+    pub fn insert_group(&mut self, key: String, members: Vec<String>) -> Option<Vec<String>> {
+        self.groups.insert(key, members)
+    }
+
+    pub fn remove_group(&mut self, key: &str) -> Option<Vec<String>> {
+        self.groups.remove(key)
+    }
+
+    /// Looks up a character with the overrides for the locale applied. A regional locale like
+    /// `"es-MX"` also uses the overrides of its language, `"es"`.
+    pub fn get_localized(&self, key: &str, locale: &str) -> Option<Cow<'_, GrammaticalCharacter>> {
+        let character = self.get(key)?;
+
+        // The language is applied first so that the region can refine it
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        let locales = if language == locale {
+            vec![locale]
+        } else {
+            vec![language, locale]
+        };
+
+        let overrides: Vec<_> = locales
+            .into_iter()
+            .filter_map(|locale| self.localization(locale, key))
+            .collect();
+
+        if overrides.is_empty() {
+            return Some(Cow::Borrowed(character));
+        }
+
+        Some(Cow::Owned(
+            overrides
+                .into_iter()
+                .fold(character.clone(), |character, localization| {
+                    character.localized(localization)
+                }),
+        ))
+    }
+
+    pub fn localization(&self, locale: &str, key: &str) -> Option<&CharacterLocalization> {
+        self.localizations.get(locale)?.get(key)
+    }
+
+    /// Overrides the data of a character in the given locale.
+    pub fn insert_localization(
+        &mut self,
+        locale: String,
+        key: String,
+        localization: CharacterLocalization,
+    ) -> Option<CharacterLocalization> {
+        self.localizations
+            .entry(locale)
+            .or_default()
+            .insert(key, localization)
+    }
+
+    pub fn remove_localization(
+        &mut self,
+        locale: &str,
+        key: &str,
+    ) -> Option<CharacterLocalization> {
+        self.localizations.get_mut(locale)?.remove(key)
+    }
+}
+
+/// The data of a character that differs in one locale, e.g. a translated name, a title the
+/// player chose, or the pronouns to use in a language. Unset fields keep the default data.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CharacterLocalization {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pronouns: Option<Pronouns>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<Title>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    person_descriptor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gender: Option<Gender>,
+}
+
+impl CharacterLocalization {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn with_pronouns(mut self, pronouns: Pronouns) -> Self {
+        self.pronouns = Some(pronouns);
+        self
+    }
+
+    pub fn with_title(mut self, title: Title) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    pub fn with_person_descriptor(mut self, person_descriptor: String) -> Self {
+        self.person_descriptor = Some(person_descriptor);
+        self
+    }
+
+    pub fn with_gender(mut self, gender: Gender) -> Self {
+        self.gender = Some(gender);
+        self
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Pronouns {
     HeHim,
     SheHer,
@@ -67,6 +245,7 @@ impl Pronouns {
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum Title {
     Mr,
     Ms,
@@ -82,6 +261,8 @@ impl Title {
         Self::Custom(title)
     }
 
+    /// The English abbreviation of the title. Grammars translate titles with `Grammar::title`,
+    /// and `CharacterLocalization` can replace them per locale.
     pub fn str(&self) -> &str {
         match self {
             Title::Mr => "Mr.",
@@ -94,18 +275,26 @@ impl Title {
     }
 }
 
-impl ToString for Title {
-    fn to_string(&self) -> String {
-        self.str().to_string()
+impl Display for Title {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.str())
     }
 }
 
 #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GrammaticalCharacter {
     name: String,
     pronouns: Pronouns,
     title: Option<Title>,
     person_descriptor: Option<String>,
+    /// The grammatical gender, if it differs from the one the pronouns suggest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gender: Option<Gender>,
+    /// The grammatical person the character is referred to in while rendering, e.g. the first
+    /// person for the speaker. Not part of the cast.
+    #[serde(skip)]
+    perspective: Option<ConjugatePerson>,
 }
 
 impl GrammaticalCharacter {
@@ -120,13 +309,52 @@ impl GrammaticalCharacter {
             pronouns,
             title,
             person_descriptor,
+            gender: None,
+            perspective: None,
         }
     }
 
+    /// Sets the grammatical gender that articles and adjectives agree with.
+    pub fn with_gender(mut self, gender: Gender) -> Self {
+        self.gender = Some(gender);
+        self
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn pronouns(&self) -> &Pronouns {
+        &self.pronouns
+    }
+
+    pub fn set_pronouns(&mut self, pronouns: Pronouns) {
+        self.pronouns = pronouns;
+    }
+
+    /// Refers to the character in a grammatical person, e.g. "I" and "me" for the first person
+    /// singular, instead of by their pronouns.
+    pub fn with_perspective(mut self, person: ConjugatePerson) -> Self {
+        self.perspective = Some(person);
+        self
+    }
+
+    pub fn perspective(&self) -> Option<ConjugatePerson> {
+        self.perspective
+    }
+
+    pub fn set_perspective(&mut self, person: Option<ConjugatePerson>) {
+        self.perspective = person;
+    }
+
+    pub fn gender(&self) -> Option<Gender> {
+        self.gender
+    }
+
+    pub fn set_gender(&mut self, gender: Option<Gender>) {
+        self.gender = gender;
+    }
+
     pub fn title(&self) -> Option<&Title> {
         self.title.as_ref()
     }
@@ -135,110 +363,67 @@ impl GrammaticalCharacter {
         self.person_descriptor.as_ref()
     }
 
-    pub fn subjective_pronoun(&self) -> String {
-        match &self.pronouns {
-            Pronouns::HeHim => "he",
-            Pronouns::SheHer => "she",
-            Pronouns::ItIts => "it",
-            Pronouns::TheyThem => "they",
-            Pronouns::Name => &self.name,
-            Pronouns::XeXyr => "xe",
-            Pronouns::Custom { subjective, .. } => subjective,
+    /// The character with the overrides of a locale applied.
+    pub fn localized(&self, localization: &CharacterLocalization) -> Self {
+        let mut character = self.clone();
+
+        if let Some(name) = &localization.name {
+            character.name = name.clone();
         }
-        .to_string()
+
+        if let Some(pronouns) = &localization.pronouns {
+            character.pronouns = pronouns.clone();
+        }
+
+        if let Some(title) = &localization.title {
+            character.title = Some(title.clone());
+        }
+
+        if let Some(person_descriptor) = &localization.person_descriptor {
+            character.person_descriptor = Some(person_descriptor.clone());
+        }
+
+        if localization.gender.is_some() {
+            character.gender = localization.gender;
+        }
+
+        character
+    }
+
+    // The following methods use the English grammar. See `Grammar` for other languages.
+
+    pub fn subjective_pronoun(&self) -> String {
+        English.pronoun(self, PronounForm::Subjective, &Inflection::default())
     }
 
     pub fn objective_pronoun(&self) -> String {
-        match &self.pronouns {
-            Pronouns::HeHim => "him",
-            Pronouns::SheHer => "her",
-            Pronouns::ItIts => "it",
-            Pronouns::TheyThem => "them",
-            Pronouns::Name => &self.name,
-            Pronouns::XeXyr => "xem",
-            Pronouns::Custom { objective, .. } => objective,
-        }
-        .to_string()
+        English.pronoun(self, PronounForm::Objective, &Inflection::default())
     }
 
     pub fn possessive_determiner(&self) -> String {
-        match &self.pronouns {
-            Pronouns::HeHim => "his".to_string(),
-            Pronouns::SheHer => "her".to_string(),
-            Pronouns::ItIts => "its".to_string(),
-            Pronouns::TheyThem => "their".to_string(),
-            Pronouns::Name => {
-                let name_ends_in_s = matches!(
-                    self.name.chars().last().map(|c| c.to_ascii_lowercase()),
-                    Some('s')
-                );
-
-                let name = &self.name;
-                let end_char = if name_ends_in_s { "" } else { "s" };
-
-                format!("{name}'{end_char}")
-            }
-            Pronouns::XeXyr => "xyr".to_string(),
-            Pronouns::Custom {
-                possessive_determiner,
-                ..
-            } => possessive_determiner.to_string(),
-        }
+        English.pronoun(
+            self,
+            PronounForm::PossessiveDeterminer,
+            &Inflection::default(),
+        )
     }
 
     pub fn possessive_pronoun(&self) -> String {
-        match &self.pronouns {
-            Pronouns::HeHim => "his".to_string(),
-            Pronouns::SheHer => "hers".to_string(),
-            Pronouns::ItIts => "its".to_string(),
-            Pronouns::TheyThem => "theirs".to_string(),
-            Pronouns::Name => {
-                let name_ends_in_s = matches!(
-                    self.name.chars().last().map(|c| c.to_ascii_lowercase()),
-                    Some('s')
-                );
-
-                let name = &self.name;
-                let end_char = if name_ends_in_s { "" } else { "s" };
-
-                format!("{name}'{end_char}")
-            }
-            Pronouns::XeXyr => "xyrs".to_string(),
-            Pronouns::Custom { possessive, .. } => possessive.to_string(),
-        }
+        English.pronoun(self, PronounForm::Possessive, &Inflection::default())
     }
 
     pub fn reflexive_pronoun(&self) -> String {
-        match &self.pronouns {
-            Pronouns::HeHim => "himself".to_string(),
-            Pronouns::SheHer => "herself".to_string(),
-            Pronouns::ItIts => "itself".to_string(),
-            Pronouns::TheyThem => "themself".to_string(),
-            Pronouns::Name => {
-                let name_ends_in_s = matches!(
-                    self.name.chars().last().map(|c| c.to_ascii_lowercase()),
-                    Some('s')
-                );
-
-                let name = &self.name;
-                let end_char = if name_ends_in_s { "" } else { "s" };
-
-                // FIXME: find a good solution for this case
-                format!("{name}'{end_char} self")
-            }
-            Pronouns::XeXyr => "xyrself".to_string(),
-            Pronouns::Custom { reflexive, .. } => reflexive.to_string(),
-        }
+        English.pronoun(self, PronounForm::Reflexive, &Inflection::default())
+    }
+
+    /// Combines several characters into one plural subject. Its name is the list of the members'
+    /// names and it uses plural they/them pronouns.
+    pub fn group(members: &[&GrammaticalCharacter]) -> Self {
+        English.group(members)
     }
 
     pub fn conjugate_case(&self) -> ConjugatePerson {
-        use ConjugatePerson::*;
-        use Pronouns::*;
-        match &self.pronouns {
-            HeHim | SheHer | ItIts | XeXyr | Name => ThirdSingular,
-            TheyThem => ThirdPlural,
-            Pronouns::Custom { conjugate_case, .. } => *conjugate_case,
-        }
+        English.conjugate_person(self)
     }
 }
 
@@ -256,7 +441,6 @@ impl Default for GrammaticalCharacter {
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
-    type Res = Result<(), crate::Error>;
 
     pub(crate) fn get_characters() -> [GrammaticalCharacter; 4] {
         let pidge = GrammaticalCharacter {
@@ -264,6 +448,8 @@ pub(crate) mod tests {
             pronouns: Pronouns::TheyThem,
             title: Some(Title::NoTitle),
             person_descriptor: Some("Person".to_string()),
+            gender: None,
+            perspective: None,
         };
 
         let alfons = GrammaticalCharacter {
@@ -271,6 +457,8 @@ pub(crate) mod tests {
             pronouns: Pronouns::Name,
             title: Some(Title::Custom("King".into())),
             person_descriptor: Some("Man".to_string()),
+            gender: None,
+            perspective: None,
         };
 
         let tupo = GrammaticalCharacter {
@@ -278,6 +466,8 @@ pub(crate) mod tests {
             pronouns: Pronouns::XeXyr,
             title: Some(Title::NoTitle),
             person_descriptor: Some("Laru".to_string()),
+            gender: None,
+            perspective: None,
         };
 
         let hunk = GrammaticalCharacter {
@@ -285,6 +475,8 @@ pub(crate) mod tests {
             pronouns: Pronouns::HeHim,
             title: Some(Title::Mr),
             person_descriptor: Some("Man".to_string()),
+            gender: None,
+            perspective: None,
         };
 
         [pidge, alfons, tupo, hunk]
@@ -303,6 +495,69 @@ pub(crate) mod tests {
         cast
     }
 
+    #[test]
+    fn group_test() {
+        let [pidge, alfons, _, hunk] = get_characters();
+
+        let group = GrammaticalCharacter::group(&[&pidge, &hunk, &alfons]);
+
+        assert_eq!(group.name(), "Pidge, Hunk and Alfons");
+        assert_eq!(group.subjective_pronoun(), "they");
+        assert_eq!(group.possessive_determiner(), "their");
+        assert_eq!(group.reflexive_pronoun(), "themselves");
+        assert_eq!(group.conjugate_case(), ConjugatePerson::ThirdPlural);
+
+        // The perspective is only used while rendering and isn't saved with the character
+        let json = serde_json::to_string(&group).unwrap();
+        assert!(!json.contains("Third"));
+        let group: GrammaticalCharacter = serde_json::from_str(&json).unwrap();
+        assert_eq!(group.perspective(), None);
+
+        assert_eq!(English.join_list(&["Pidge".to_string()], None), "Pidge");
+        assert_eq!(
+            English.join_list(&["Pidge".to_string(), "Hunk".to_string()], Some("or")),
+            "Pidge or Hunk"
+        );
+    }
+
+    #[test]
+    fn localization_test() {
+        let mut cast = gen_cast();
+
+        cast.insert_localization(
+            "de".to_string(),
+            "hunk".to_string(),
+            CharacterLocalization::new().with_person_descriptor("Mann".to_string()),
+        );
+        cast.insert_localization(
+            "de-CH".to_string(),
+            "hunk".to_string(),
+            CharacterLocalization::new().with_title(Title::Custom("Herr Dr.".to_string())),
+        );
+
+        let hunk = cast.get_localized("hunk", "de-CH").unwrap();
+        assert_eq!(hunk.person_descriptor().unwrap(), "Mann");
+        assert_eq!(hunk.title(), Some(&Title::Custom("Herr Dr.".to_string())));
+
+        let hunk = cast.get_localized("hunk", "de").unwrap();
+        assert_eq!(hunk.title(), Some(&Title::Mr));
+
+        // Characters without overrides are borrowed as they are
+        assert!(matches!(
+            cast.get_localized("pidge", "de-CH"),
+            Some(Cow::Borrowed(_))
+        ));
+
+        assert!(cast.remove_localization("de", "hunk").is_some());
+        assert_eq!(
+            cast.get_localized("hunk", "de-CH")
+                .unwrap()
+                .person_descriptor()
+                .unwrap(),
+            "Man"
+        );
+    }
+
     #[test]
     fn no_pronoun_test() {
         let [pidge, alfons, tupo, _] = get_characters();
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use crate::{character::Pronouns, variables::Variables};
+
+/// Per render call state that is not part of the compiler itself, e.g. the current game state.
+#[derive(Default, Clone)]
+pub struct RenderContext<'r> {
+    variables: Option<&'r dyn Variables>,
+    groups: HashMap<String, Vec<String>>,
+    first_person: Option<String>,
+    second_person: Option<String>,
+    pronouns: HashMap<String, Pronouns>,
+}
+
+impl<'r> RenderContext<'r> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_variables(mut self, variables: &'r dyn Variables) -> Self {
+        self.variables = Some(variables);
+        self
+    }
+
+    /// Adds a group of characters that only exists for this render call. It takes precedence
+    /// over a group with the same name in the `CharacterCast`.
+    pub fn with_group(mut self, key: String, members: Vec<String>) -> Self {
+        self.groups.insert(key, members);
+        self
+    }
+
+    /// Sets the character that is "I", e.g. the speaker of a line.
+    pub fn with_first_person(mut self, key: String) -> Self {
+        self.first_person = Some(key);
+        self
+    }
+
+    /// Sets the character that is "you", e.g. the player in a game narrated in second person.
+    pub fn with_second_person(mut self, key: String) -> Self {
+        self.second_person = Some(key);
+        self
+    }
+
+    /// Renders a character with other pronouns for this render call, e.g. to preview a line with
+    /// every pronoun set a player can choose.
+    pub fn with_pronouns(mut self, key: String, pronouns: Pronouns) -> Self {
+        self.pronouns.insert(key, pronouns);
+        self
+    }
+
+    pub fn variables(&self) -> Option<&'r dyn Variables> {
+        self.variables
+    }
+
+    pub(crate) fn group_keys(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    pub fn group(&self, key: &str) -> Option<&[String]> {
+        self.groups.get(key).map(Vec::as_slice)
+    }
+
+    pub fn first_person(&self) -> Option<&str> {
+        self.first_person.as_deref()
+    }
+
+    pub fn second_person(&self) -> Option<&str> {
+        self.second_person.as_deref()
+    }
+
+    pub fn pronouns(&self, key: &str) -> Option<&Pronouns> {
+        self.pronouns.get(key)
+    }
+}
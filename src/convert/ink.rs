@@ -0,0 +1,531 @@
+use super::{label, Builder, Choice, Conversion, Segment, Statement, WarningKind};
+use crate::{document::START_LABEL, Error};
+
+/// A knot, a stitch or the content in front of the first knot.
+struct Container<'s> {
+    name: String,
+    knot: &'s str,
+    /// The stitch a knot without content of its own continues with.
+    first_stitch: Option<String>,
+    lines: Vec<(usize, &'s str)>,
+}
+
+enum InkLine<'s> {
+    Choice {
+        depth: usize,
+        content: &'s str,
+        number: usize,
+    },
+    Gather {
+        depth: usize,
+        content: &'s str,
+        number: usize,
+    },
+    Content {
+        content: &'s str,
+        number: usize,
+    },
+}
+
+/// Converts an Ink script into an XYR document.
+///
+/// Knots, stitches, choices with their nested content, gathers and diverts are converted, as are
+/// variables printed like `{player_they}`. Logic, conditional and alternative text, glue, tags,
+/// threads and tunnels are reported. Content in front of the first knot becomes the node `start`.
+pub fn from_ink(src: &str) -> Result<Conversion, Error> {
+    let src = strip_block_comments(src);
+    let mut builder = Builder::new([]);
+    let containers = split_containers(&src, &mut builder);
+
+    builder
+        .labels
+        .extend(containers.iter().map(|container| label(&container.name)));
+
+    for container in &containers {
+        let lines = classify(&mut builder, &container.lines);
+        let statements = block(&mut builder, &containers, container, &lines, &mut 0, 0);
+
+        let fallback = match container.lines.is_empty() {
+            true => container.first_stitch.as_deref(),
+            false => None,
+        };
+
+        builder.node(&container.name, statements, fallback);
+    }
+
+    builder.finish()
+}
+
+/// Blanks out `/* */` comments, keeping their line breaks so that line numbers stay the same.
+fn strip_block_comments(src: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = src;
+
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+
+        let end = rest[start..]
+            .find("*/")
+            .map_or(rest.len(), |end| start + end + 2);
+        let newlines = rest[start..end].matches('\n').count();
+
+        stripped.push_str(&"\n".repeat(newlines));
+        rest = &rest[end..];
+    }
+
+    stripped.push_str(rest);
+    stripped
+}
+
+fn split_containers<'s>(src: &'s str, builder: &mut Builder) -> Vec<Container<'s>> {
+    let mut containers = vec![Container {
+        name: START_LABEL.to_string(),
+        knot: "",
+        first_stitch: None,
+        lines: Vec::new(),
+    }];
+    let mut in_function = false;
+
+    for (index, line) in src.lines().enumerate() {
+        let number = index + 1;
+        let line = match line.find("//") {
+            Some(comment) => &line[..comment],
+            None => line,
+        }
+        .trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with("==") {
+            let name = line.trim_matches('=').trim();
+            in_function = name.starts_with("function ");
+
+            if in_function {
+                builder.warn(number, WarningKind::UnsupportedSyntax, line);
+                continue;
+            }
+
+            let name = match name.split_once('(') {
+                Some((name, _)) => {
+                    builder.warn(number, WarningKind::UnsupportedSyntax, line);
+                    name.trim()
+                }
+                None => name,
+            };
+
+            containers.push(Container {
+                name: name.to_string(),
+                knot: name,
+                first_stitch: None,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        if in_function {
+            continue;
+        }
+
+        if let Some(stitch) = line.strip_prefix('=') {
+            let knot = containers.last().map_or("", |container| container.knot);
+            let name = format!("{knot}.{}", stitch.trim());
+
+            if let Some(container) = containers.iter_mut().rev().find(|c| c.name == knot) {
+                container.first_stitch.get_or_insert_with(|| name.clone());
+            }
+
+            containers.push(Container {
+                name,
+                knot,
+                first_stitch: None,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+
+        match keyword {
+            // Declarations only give variables their initial values
+            "VAR" | "CONST" => {}
+            "INCLUDE" | "EXTERNAL" | "LIST" => {
+                builder.warn(number, WarningKind::UnsupportedSyntax, line)
+            }
+            _ if line.starts_with('~') => {
+                builder.warn(number, WarningKind::UnsupportedSyntax, line)
+            }
+            _ => {
+                if let Some(container) = containers.last_mut() {
+                    container.lines.push((number, line));
+                }
+            }
+        }
+    }
+
+    // Stories that start with a divert don't need a start node
+    if containers[0].lines.is_empty() && containers.len() > 1 {
+        containers.remove(0);
+    }
+
+    containers
+}
+
+fn classify<'s>(builder: &mut Builder, lines: &[(usize, &'s str)]) -> Vec<InkLine<'s>> {
+    lines
+        .iter()
+        .map(|&(number, line)| {
+            let (depth, content) = markers(line, |rest| rest.starts_with(['*', '+']));
+
+            if depth > 0 {
+                return InkLine::Choice {
+                    depth,
+                    content: strip_condition(builder, strip_label(content), number),
+                    number,
+                };
+            }
+
+            let (depth, content) = markers(line, |rest| {
+                rest.starts_with('-') && !rest.starts_with("->")
+            });
+
+            if depth > 0 {
+                return InkLine::Gather {
+                    depth,
+                    content: strip_label(content),
+                    number,
+                };
+            }
+
+            InkLine::Content {
+                content: line,
+                number,
+            }
+        })
+        .collect()
+}
+
+/// Counts the choice or gather markers at the start of a line: `* *` and `- -` are both nested
+/// two levels deep.
+fn markers(line: &str, is_marker: impl Fn(&str) -> bool) -> (usize, &str) {
+    let mut depth = 0;
+    let mut rest = line;
+
+    while is_marker(rest) {
+        depth += 1;
+        rest = rest[1..].trim_start();
+    }
+
+    (depth, rest)
+}
+
+/// Removes the label from the start of a choice or gather: `(greeting)`.
+fn strip_label(content: &str) -> &str {
+    match content
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+    {
+        Some((_, rest)) => rest.trim_start(),
+        None => content,
+    }
+}
+
+/// Removes the conditions from the start of a choice: `{met_pidge}`.
+fn strip_condition<'s>(builder: &mut Builder, content: &'s str, number: usize) -> &'s str {
+    let mut content = content;
+
+    while content.starts_with('{') {
+        let Some(end) = content.find('}') else {
+            break;
+        };
+
+        builder.warn(number, WarningKind::UnsupportedSyntax, &content[..=end]);
+        content = content[end + 1..].trim_start();
+    }
+
+    content
+}
+
+fn block(
+    builder: &mut Builder,
+    containers: &[Container],
+    container: &Container,
+    lines: &[InkLine],
+    pos: &mut usize,
+    level: usize,
+) -> Vec<Statement> {
+    let mut statements = Vec::new();
+
+    while let Some(line) = lines.get(*pos) {
+        match *line {
+            InkLine::Choice { depth, .. } | InkLine::Gather { depth, .. } if depth <= level => {
+                break;
+            }
+            InkLine::Choice { depth, .. } => {
+                let mut choices = Vec::new();
+
+                while let Some(&InkLine::Choice {
+                    depth: choice_depth,
+                    content,
+                    number,
+                }) = lines.get(*pos)
+                {
+                    if choice_depth != depth {
+                        break;
+                    }
+
+                    *pos += 1;
+
+                    let (content, divert) = split_divert(content);
+
+                    // "Hello [there] friend" shows "Hello there" and then says "Hello friend"
+                    let (text, echo) = match content.split_once('[') {
+                        Some((before, rest)) => match rest.split_once(']') {
+                            Some((inside, after)) => {
+                                (format!("{before}{inside}"), format!("{before}{after}"))
+                            }
+                            None => (content.to_string(), content.to_string()),
+                        },
+                        None => (content.to_string(), content.to_string()),
+                    };
+
+                    let mut body = Vec::new();
+
+                    push_content(builder, &mut body, &echo, None, number);
+
+                    if let Some(target) = divert {
+                        body.push(divert_statement(containers, container, target, number));
+                    }
+
+                    body.extend(block(builder, containers, container, lines, pos, depth));
+
+                    choices.push(Choice {
+                        text: builder.text(&segments(&text), number),
+                        body,
+                    });
+                }
+
+                statements.push(Statement::Choices(choices));
+            }
+            InkLine::Gather {
+                content, number, ..
+            }
+            | InkLine::Content { content, number } => {
+                *pos += 1;
+
+                let (content, divert) = split_divert(content);
+                let divert =
+                    divert.map(|target| divert_statement(containers, container, target, number));
+
+                push_content(builder, &mut statements, content, divert, number);
+            }
+        }
+    }
+
+    statements
+}
+
+fn push_content(
+    builder: &mut Builder,
+    statements: &mut Vec<Statement>,
+    content: &str,
+    divert: Option<Statement>,
+    number: usize,
+) {
+    let content = match content.find('#') {
+        Some(tags) => {
+            builder.warn(number, WarningKind::UnsupportedSyntax, &content[tags..]);
+            &content[..tags]
+        }
+        None => content,
+    };
+
+    let content = if content.contains("<>") {
+        builder.warn(number, WarningKind::UnsupportedSyntax, "<>");
+        content.replace("<>", "")
+    } else {
+        content.to_string()
+    };
+
+    let text = builder.text(&segments(&content), number);
+
+    if !text.is_empty() {
+        statements.push(Statement::Line {
+            speaker: None,
+            text,
+        });
+    }
+
+    statements.extend(divert);
+}
+
+fn split_divert(content: &str) -> (&str, Option<&str>) {
+    match content.split_once("->") {
+        Some((content, target)) => (content, Some(target.trim())),
+        None => (content, None),
+    }
+}
+
+/// Resolves a divert target relative to the knot it is in.
+fn divert_statement(
+    containers: &[Container],
+    container: &Container,
+    target: &str,
+    number: usize,
+) -> Statement {
+    if matches!(target, "END" | "DONE") {
+        return Statement::End;
+    }
+
+    let stitch = format!("{}.{target}", container.knot);
+    let target = match containers.iter().any(|container| container.name == stitch) {
+        true => stitch,
+        false => target.to_string(),
+    };
+
+    Statement::Jump {
+        target,
+        line: number,
+    }
+}
+
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(index) = rest.find(['{', '\\']) {
+        segments.push(Segment::Text(&rest[..index]));
+        rest = &rest[index..];
+
+        if rest.starts_with('\\') {
+            let escaped = rest[1..].chars().next().map_or(0, char::len_utf8);
+            segments.push(Segment::Text(&rest[1..1 + escaped]));
+            rest = &rest[1 + escaped..];
+            continue;
+        }
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        let (expression, remainder) = rest.split_at(end + 1);
+        let name = expression[1..end].trim();
+
+        segments.push(
+            if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                Segment::Variable(name)
+            } else {
+                Segment::Unsupported(expression)
+            },
+        );
+        rest = remainder;
+    }
+
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, convert::ConversionWarning, verbs, DialogEvent, DialogMacroCompiler,
+        DialogRunner, NodeExit, RenderedLine,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    const SCRIPT: &str = r#"VAR pidge_they = "they"
+-> cafeteria
+
+=== cafeteria ===
+Hey! Have you met {pidge_name}? // The player hasn't
+* [Who?]
+    {pidge_They} {pidge_are} our engineer.
+    * * [Nice.] -> DONE
+    * * Tell me more about {pidge_their} work.
+        ~ curious = true
+        -> lab
+* [Bye.] -> END
+- Anyway, tell {pidge_her} that lunch is ready.
+-> END
+
+=== lab ===
+= entrance
+Welcome to the lab. #music
+-> END
+"#;
+
+    #[test]
+    fn ink_test() -> Res {
+        let conversion = from_ink(SCRIPT)?;
+        let document = conversion.document();
+
+        assert_eq!(document.nodes()[0].label(), "start");
+        assert_eq!(document.nodes()[1].label(), "cafeteria");
+        assert_eq!(
+            document.node("lab").unwrap().exit(),
+            &NodeExit::Jump("lab/entrance".to_string())
+        );
+
+        assert_eq!(
+            conversion
+                .warnings()
+                .iter()
+                .map(|warning| (warning.line(), warning.kind()))
+                .collect::<Vec<_>>(),
+            [
+                (10, WarningKind::UnsupportedSyntax),
+                (13, WarningKind::AmbiguousPronoun),
+                (18, WarningKind::UnsupportedSyntax),
+            ]
+        );
+        assert_eq!(
+            conversion.warnings().last().map(ConversionWarning::source),
+            Some("#music")
+        );
+
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict());
+        let mut runner = DialogRunner::new(&compiler, document);
+
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: None,
+                text: "Hey! Have you met Pidge?".to_string(),
+            })
+        );
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Choices(vec!["Who?".to_string(), "Bye.".to_string()])
+        );
+
+        runner.choose(0)?;
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: None,
+                text: "They are our engineer.".to_string(),
+            })
+        );
+
+        // The echo of the second choice is said before diverting to the lab
+        runner.advance()?;
+        runner.choose(1)?;
+        assert!(matches!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine { text, .. }) if text.starts_with("Tell me more about")
+        ));
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: None,
+                text: "Welcome to the lab.".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+}
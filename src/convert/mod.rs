@@ -0,0 +1,360 @@
+//! Importers that translate dialog written for other narrative scripting languages into XYR.
+//!
+//! Scripts in these languages usually handle pronouns with ad-hoc variables like `$player_they`
+//! or `$player_Their`. Variables that follow the `<character>_<pronoun>` convention become
+//! pronoun macros for that character, other variables become `Variable` macros. Anything that
+//! has no XYR equivalent is kept as plain text and reported as a [`ConversionWarning`].
+
+mod ink;
+mod twine;
+mod yarn;
+
+use std::collections::HashSet;
+
+use crate::{
+    dialog_parser::{DialogMacro, DialogMacroMod, DialogMacroType},
+    document::{DialogChoice, DialogLine, DialogNode, NodeExit, XyrDocument, END_LABEL},
+    Error,
+};
+
+pub use ink::from_ink;
+pub use twine::from_twine;
+pub use yarn::from_yarn;
+
+/// The result of importing a script: the converted document and everything that could not be
+/// converted faithfully.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    document: XyrDocument,
+    warnings: Vec<ConversionWarning>,
+}
+
+impl Conversion {
+    pub fn document(&self) -> &XyrDocument {
+        &self.document
+    }
+
+    pub fn into_document(self) -> XyrDocument {
+        self.document
+    }
+
+    pub fn warnings(&self) -> &[ConversionWarning] {
+        &self.warnings
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionWarning {
+    line: usize,
+    kind: WarningKind,
+    source: String,
+}
+
+impl ConversionWarning {
+    /// The line of the imported script, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+
+    /// The part of the script that caused the warning.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// A command, expression or markup without an XYR equivalent. Expressions and markup are kept
+    /// as plain text, commands are dropped.
+    UnsupportedSyntax,
+    /// A pronoun variable that can't be mapped to a single pronoun form, like `$player_her`. It
+    /// is converted to a `Variable` macro.
+    AmbiguousPronoun,
+    /// A jump to a node that doesn't exist. It is converted to a jump to `END`.
+    UnknownTarget,
+}
+
+/// A piece of dialog text as read by an importer.
+enum Segment<'s> {
+    Text(&'s str),
+    Variable(&'s str),
+    Unsupported(&'s str),
+}
+
+/// The format independent structure of a node, before it is split into XYR nodes.
+enum Statement {
+    Line {
+        speaker: Option<String>,
+        text: String,
+    },
+    Jump {
+        target: String,
+        line: usize,
+    },
+    End,
+    Choices(Vec<Choice>),
+}
+
+struct Choice {
+    text: String,
+    body: Vec<Statement>,
+}
+
+/// Lowers the statements of every imported node into XYR nodes.
+///
+/// XYR choices can only jump to other nodes, so the body of a choice and the statements after a
+/// set of choices move to new nodes, which are labeled after the node they come from.
+struct Builder {
+    labels: HashSet<String>,
+    nodes: Vec<Option<DialogNode>>,
+    warnings: Vec<ConversionWarning>,
+}
+
+impl Builder {
+    /// Creates a builder for a script with the given node labels.
+    fn new<'l>(labels: impl IntoIterator<Item = &'l str>) -> Self {
+        Self {
+            labels: labels.into_iter().map(label).collect(),
+            nodes: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn warn(&mut self, line: usize, kind: WarningKind, source: &str) {
+        self.warnings.push(ConversionWarning {
+            line,
+            kind,
+            source: source.trim().to_string(),
+        });
+    }
+
+    /// Converts dialog text into an XYR template.
+    fn text(&mut self, segments: &[Segment], line: usize) -> String {
+        let mut template = String::new();
+
+        for segment in segments {
+            match segment {
+                Segment::Text(text) => template.push_str(&escape(text)),
+                Segment::Variable(name) => template.push_str(&self.variable(name, line)),
+                Segment::Unsupported(source) => {
+                    self.warn(line, WarningKind::UnsupportedSyntax, source);
+                    template.push_str(&escape(source));
+                }
+            }
+        }
+
+        template.trim().to_string()
+    }
+
+    fn variable(&mut self, name: &str, line: usize) -> String {
+        let variable = DialogMacro::new(None, DialogMacroType::Variable, Some(name), Vec::new());
+
+        let Some((character, suffix)) = name.rsplit_once('_') else {
+            return macro_json(&variable);
+        };
+
+        let (_type, data) = match suffix.to_lowercase().as_str() {
+            "they" | "he" | "she" | "xe" | "subjective" => {
+                (DialogMacroType::SubjectivePronoun, None)
+            }
+            "them" | "him" | "xem" | "objective" => (DialogMacroType::ObjectivePronoun, None),
+            "their" | "xyr" => (DialogMacroType::PossessiveDeterminer, None),
+            "theirs" | "hers" | "xyrs" => (DialogMacroType::PossessivePronoun, None),
+            "themself" | "themselves" | "himself" | "herself" | "xemself" | "reflexive" => {
+                (DialogMacroType::ReflexivePronoun, None)
+            }
+            "name" => (DialogMacroType::Name, None),
+            "descriptor" => (DialogMacroType::PersonDescriptor, None),
+            "are" | "is" => (DialogMacroType::VerbConjugate, Some("to be")),
+            "have" | "has" => (DialogMacroType::VerbConjugate, Some("to have")),
+            "his" | "her" => {
+                self.warn(line, WarningKind::AmbiguousPronoun, name);
+                return macro_json(&variable);
+            }
+            _ => return macro_json(&variable),
+        };
+
+        let mut mods = Vec::new();
+
+        if suffix.len() > 1 && suffix.chars().all(|c| c.is_uppercase()) {
+            mods.push(DialogMacroMod::UpperCase);
+        } else if suffix.starts_with(char::is_uppercase) {
+            mods.push(DialogMacroMod::Capitalized);
+        }
+
+        macro_json(&DialogMacro::new(Some(character), _type, data, mods))
+    }
+
+    /// Writes a node and the nodes split off from it.
+    fn node(&mut self, name: &str, statements: Vec<Statement>, fallback: Option<&str>) {
+        self.lower(label(name), statements, fallback.map(label));
+    }
+
+    /// Writes a node that continues with `fallback`, or ends the conversation, if its statements
+    /// don't jump elsewhere.
+    fn lower(&mut self, label: String, statements: Vec<Statement>, fallback: Option<String>) {
+        // Nodes go in front of the nodes split off from them, to keep the document in reading order
+        let slot = self.nodes.len();
+        self.nodes.push(None);
+
+        let mut lines = Vec::new();
+        let mut exit = None;
+        let mut statements = statements.into_iter();
+
+        while let Some(statement) = statements.next() {
+            match statement {
+                Statement::Line { speaker, text } => lines.push(DialogLine::new(speaker, text)),
+                Statement::Jump { target, line } => {
+                    exit = Some(jump(self.target(&target, line)));
+                    break;
+                }
+                Statement::End => {
+                    exit = Some(NodeExit::End);
+                    break;
+                }
+                Statement::Choices(choices) => {
+                    let rest: Vec<Statement> = statements.collect();
+
+                    let next = if rest.is_empty() {
+                        fallback.clone()
+                    } else {
+                        let next = self.fresh_label(&label);
+                        self.lower(next.clone(), rest, fallback.clone());
+                        Some(next)
+                    };
+
+                    let mut options = Vec::new();
+
+                    for choice in choices {
+                        let target = match choice.body.as_slice() {
+                            [] => next.clone().unwrap_or_else(|| END_LABEL.to_string()),
+                            [Statement::Jump { target, line }] => self.target(target, *line),
+                            [Statement::End] => END_LABEL.to_string(),
+                            _ => {
+                                let body = self.fresh_label(&label);
+                                self.lower(body.clone(), choice.body, next.clone());
+                                body
+                            }
+                        };
+
+                        options.push(DialogChoice::new(choice.text, target));
+                    }
+
+                    exit = Some(NodeExit::Choices(options));
+                    break;
+                }
+            }
+        }
+
+        let exit = exit.unwrap_or_else(|| match fallback {
+            Some(fallback) => jump(fallback),
+            None => NodeExit::End,
+        });
+
+        self.nodes[slot] = Some(DialogNode::from_parts(label, lines, exit));
+    }
+
+    fn target(&mut self, target: &str, line: usize) -> String {
+        let target = label(target);
+
+        if self.labels.contains(&target) {
+            target
+        } else {
+            self.warn(line, WarningKind::UnknownTarget, &target);
+            END_LABEL.to_string()
+        }
+    }
+
+    fn fresh_label(&mut self, base: &str) -> String {
+        let base = base.split('~').next().unwrap_or(base);
+
+        let label = (1..)
+            .map(|index| format!("{base}~{index}"))
+            .find(|label| !self.labels.contains(label))
+            .unwrap_or_default();
+
+        self.labels.insert(label.clone());
+        label
+    }
+
+    fn finish(self) -> Result<Conversion, Error> {
+        Ok(Conversion {
+            document: XyrDocument::from_nodes(self.nodes.into_iter().flatten().collect())?,
+            warnings: self.warnings,
+        })
+    }
+}
+
+fn jump(target: String) -> NodeExit {
+    if target == END_LABEL {
+        NodeExit::End
+    } else {
+        NodeExit::Jump(target)
+    }
+}
+
+/// Turns a node name into an XYR label, which can't contain whitespace or dots or be `END`.
+/// Dots become slashes, so the stitch `lab.entrance` is labeled `lab/entrance`.
+fn label(name: &str) -> String {
+    let label: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '.' => '/',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect();
+
+    if label == END_LABEL {
+        format!("{label}_")
+    } else {
+        label
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+fn macro_json(macr: &DialogMacro) -> String {
+    serde_json::to_string(macr).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variable_test() {
+        let mut builder = Builder::new([]);
+
+        assert_eq!(
+            builder.variable("player_They", 1),
+            r#"{"character_id":"player","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]}"#
+        );
+        assert_eq!(
+            builder.variable("npc_ally_are", 1),
+            r#"{"character_id":"npc_ally","type":"VerbConjugate","data":"to be","mods":[]}"#
+        );
+        assert_eq!(
+            builder.variable("score", 1),
+            r#"{"character_id":null,"type":"Variable","data":"score","mods":[]}"#
+        );
+        assert!(builder.warnings.is_empty());
+
+        builder.variable("player_her", 4);
+        assert_eq!(
+            builder.warnings,
+            [ConversionWarning {
+                line: 4,
+                kind: WarningKind::AmbiguousPronoun,
+                source: "player_her".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use super::{Builder, Choice, Conversion, Segment, Statement, WarningKind};
+use crate::Error;
+
+struct Passage<'s> {
+    name: &'s str,
+    lines: Vec<(usize, &'s str)>,
+}
+
+/// Passages that hold story metadata or code instead of dialog.
+const SPECIAL_PASSAGES: [&str; 2] = ["StoryTitle", "StoryData"];
+const SPECIAL_TAGS: [&str; 3] = ["script", "stylesheet", "widget"];
+
+/// Converts a Twine story in Twee 3 notation into an XYR document.
+///
+/// Every passage becomes a node whose links become choices. The start passage named in
+/// `StoryData` comes first. Variables like `$player_they` are converted; other SugarCube and
+/// Harlowe macros are reported, and macros that take up a whole line are dropped.
+pub fn from_twine(src: &str) -> Result<Conversion, Error> {
+    let (mut passages, start) = split_passages(src);
+
+    if let Some(index) = start.and_then(|start| passages.iter().position(|p| p.name == start)) {
+        let start = passages.remove(index);
+        passages.insert(0, start);
+    }
+
+    let mut builder = Builder::new(passages.iter().map(|passage| passage.name));
+
+    for passage in &passages {
+        let statements = statements(&mut builder, passage);
+        builder.node(passage.name, statements, None);
+    }
+
+    builder.finish()
+}
+
+/// Splits the story into its passages and finds the name of the start passage.
+fn split_passages(src: &str) -> (Vec<Passage<'_>>, Option<String>) {
+    let mut passages: Vec<Passage> = Vec::new();
+    let mut start = None;
+    let mut skipped = true;
+
+    for (index, line) in src.lines().enumerate() {
+        let Some(header) = line.strip_prefix("::") else {
+            if let (false, Some(passage)) = (skipped, passages.last_mut()) {
+                passage.lines.push((index + 1, line));
+            }
+            continue;
+        };
+
+        let (name, tags) = match header.split_once(" [") {
+            Some((name, rest)) => (name, rest.split_once(']').map_or("", |(tags, _)| tags)),
+            None => (header.split(" {").next().unwrap_or(header), ""),
+        };
+        let name = name.trim();
+
+        skipped = SPECIAL_PASSAGES.contains(&name)
+            || tags
+                .split_whitespace()
+                .any(|tag| SPECIAL_TAGS.contains(&tag));
+
+        if skipped {
+            // The only metadata XYR has a use for is the passage the story starts with
+            if name == "StoryData" {
+                start = src
+                    .lines()
+                    .skip(index + 1)
+                    .take_while(|line| !line.starts_with("::"))
+                    .collect::<String>()
+                    .parse::<serde_json::Value>()
+                    .ok()
+                    .and_then(|data| Some(data.get("start")?.as_str()?.to_string()));
+            }
+            continue;
+        }
+
+        passages.push(Passage {
+            name,
+            lines: Vec::new(),
+        });
+    }
+
+    (passages, start)
+}
+
+fn statements(builder: &mut Builder, passage: &Passage) -> Vec<Statement> {
+    let mut statements = Vec::new();
+    let mut choices = Vec::new();
+
+    for &(number, line) in &passage.lines {
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if is_macro(line) {
+            builder.warn(number, WarningKind::UnsupportedSyntax, line);
+            continue;
+        }
+
+        // Links become choices, and their text stays in the line
+        let mut text = String::new();
+        let mut rest = line;
+
+        while let Some(start) = rest.find("[[") {
+            let Some(end) = rest[start..].find("]]") else {
+                break;
+            };
+
+            let (label, target) = parse_link(&rest[start + 2..start + end]);
+
+            text.push_str(&rest[..start]);
+            text.push_str(label);
+
+            choices.push(Choice {
+                text: builder.text(&segments(label), number),
+                body: vec![Statement::Jump {
+                    target: target.to_string(),
+                    line: number,
+                }],
+            });
+
+            rest = &rest[start + end + 2..];
+        }
+
+        text.push_str(rest);
+
+        // A line that only holds links is a list of choices
+        if line.starts_with("[[") && line.ends_with("]]") && line.matches("[[").count() == 1 {
+            continue;
+        }
+
+        let text = builder.text(&segments(&text), number);
+
+        if !text.is_empty() {
+            statements.push(Statement::Line {
+                speaker: None,
+                text,
+            });
+        }
+    }
+
+    if !choices.is_empty() {
+        statements.push(Statement::Choices(choices));
+    }
+
+    statements
+}
+
+/// Splits a link into its text and target: `[[text|target]]`, `[[text->target]]`,
+/// `[[target<-text]]` or `[[target]]`.
+fn parse_link(link: &str) -> (&str, &str) {
+    if let Some((text, target)) = link.split_once('|') {
+        (text, target)
+    } else if let Some((text, target)) = link.rsplit_once("->") {
+        (text, target)
+    } else if let Some((target, text)) = link.split_once("<-") {
+        (text, target)
+    } else {
+        (link, link)
+    }
+}
+
+/// Whether a line only holds a SugarCube `<<macro>>` or a Harlowe `(macro: ...)`.
+fn is_macro(line: &str) -> bool {
+    (line.starts_with("<<") && line.find(">>") == Some(line.len() - 2))
+        || harlowe_macro(line) == Some(line.len())
+}
+
+/// The length of a Harlowe macro call at the start of the text, including nested parentheses.
+fn harlowe_macro(text: &str) -> Option<usize> {
+    let name = text.strip_prefix('(')?.split_once(':')?.0;
+
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    let mut depth = 0;
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 1 => return Some(index + 1),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(index) = rest.find(['$', '<', '(']) {
+        let (before, from) = rest.split_at(index);
+
+        let length = match from.as_bytes()[0] {
+            b'$' => from[1..]
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(from.len() - 1),
+            b'<' if from.starts_with("<<") => from.find(">>").map_or(0, |end| end + 2),
+            b'(' => harlowe_macro(from).unwrap_or(0),
+            _ => 0,
+        };
+
+        // Prices like $5 and other text that only looks like code
+        let is_variable = from.starts_with('$') && from[1..].starts_with(char::is_alphabetic);
+
+        if length == 0 || (from.starts_with('$') && !is_variable) {
+            segments.push(Segment::Text(&rest[..index + 1]));
+            rest = &rest[index + 1..];
+            continue;
+        }
+
+        segments.push(Segment::Text(before));
+
+        if is_variable {
+            segments.push(Segment::Variable(&from[1..=length]));
+            rest = &from[length + 1..];
+        } else {
+            segments.push(Segment::Unsupported(&from[..length]));
+            rest = &from[length..];
+        }
+    }
+
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, verbs, DialogEvent, DialogMacroCompiler, DialogRunner, NodeExit, RenderedLine,
+        XyrDocument,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    const STORY: &str = r#":: StoryTitle
+Cafeteria
+
+:: StoryData
+{
+  "ifid": "D674C58C-DEFA-4F70-B7A2-27742230C0FC",
+  "start": "Cafeteria"
+}
+
+:: Lab [quiet]
+(set: $visited to true)
+Welcome to the lab, (print: $pidge_name).
+[[Back->Cafeteria]]
+
+:: Cafeteria {"position":"100,100"}
+Hey! Have you met $pidge_name? It costs $5.
+<<if $met>>You already know $pidge_them.<</if>>
+[[Who?|Who]]
+[[Lab]]
+
+:: Who
+$pidge_They $pidge_are our [[engineer|Lab]].
+"#;
+
+    #[test]
+    fn twine_test() -> Res {
+        let conversion = from_twine(STORY)?;
+        let document = conversion.document();
+
+        assert_eq!(document.nodes()[0].label(), "Cafeteria");
+        assert_eq!(document.nodes().len(), 3);
+        assert_eq!(
+            conversion
+                .warnings()
+                .iter()
+                .map(|warning| (warning.line(), warning.kind()))
+                .collect::<Vec<_>>(),
+            [
+                (17, WarningKind::UnsupportedSyntax),
+                (17, WarningKind::UnsupportedSyntax),
+                (11, WarningKind::UnsupportedSyntax),
+                (12, WarningKind::UnsupportedSyntax),
+            ]
+        );
+
+        let NodeExit::Choices(choices) = document.node("Who").unwrap().exit() else {
+            panic!("expected choices");
+        };
+        assert_eq!(choices[0].target(), "Lab");
+
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict());
+        let mut runner = DialogRunner::new(&compiler, document);
+
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: None,
+                text: "Hey! Have you met Pidge? It costs $5.".to_string(),
+            })
+        );
+
+        runner.advance()?;
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Choices(vec!["Who?".to_string(), "Lab".to_string()])
+        );
+
+        runner.choose(0)?;
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: None,
+                text: "They are our engineer.".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+    #[test]
+    fn syntax_lines_test() -> Res {
+        // Dialog that looks like XYR syntax stays dialog
+        let story = ":: Menu\n* Tea\n-> Coffee\n=== Specials ===\nSoup:\ttomato\n[[Menu]]\n";
+        let document = from_twine(story)?.into_document();
+
+        let lines = document.node("Menu").unwrap().lines();
+        let texts: Vec<&str> = lines.iter().map(|line| line.text()).collect();
+        assert_eq!(
+            texts,
+            ["* Tea", "-> Coffee", "=== Specials ===", "Soup:\ttomato"]
+        );
+        assert!(lines.iter().all(|line| line.speaker().is_none()));
+
+        let reparsed = XyrDocument::parse(&document.to_string())?;
+        assert_eq!(reparsed.texts(), document.texts());
+
+        Ok(())
+    }
+}
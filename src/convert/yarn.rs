@@ -0,0 +1,320 @@
+use super::{Builder, Choice, Conversion, Segment, Statement, WarningKind};
+use crate::Error;
+
+struct YarnNode<'s> {
+    title: &'s str,
+    lines: Vec<YarnLine<'s>>,
+}
+
+struct YarnLine<'s> {
+    number: usize,
+    indent: usize,
+    content: &'s str,
+}
+
+/// Converts a Yarn Spinner script into an XYR document.
+///
+/// Dialog lines, `Speaker:` prefixes, options with their indented bodies, `<<jump>>` and
+/// `<<stop>>` are converted, as are variables in inline expressions like `{$player_they}`. Other
+/// commands, expressions, markup and hashtags are reported, except for `#line:` IDs.
+pub fn from_yarn(src: &str) -> Result<Conversion, Error> {
+    let nodes = split_nodes(src)?;
+    let mut builder = Builder::new(nodes.iter().map(|node| node.title));
+
+    for node in &nodes {
+        let statements = block(&mut builder, &node.lines, &mut 0, 0);
+        builder.node(node.title, statements, None);
+    }
+
+    builder.finish()
+}
+
+fn split_nodes(src: &str) -> Result<Vec<YarnNode<'_>>, Error> {
+    let mut nodes = Vec::new();
+    let mut title = None;
+    let mut body: Option<Vec<YarnLine>> = None;
+
+    for (index, line) in src.lines().enumerate() {
+        let trimmed = line.trim();
+
+        let Some(lines) = &mut body else {
+            // Header fields other than the title, like tags and positions, have no use in XYR
+            if trimmed == "---" {
+                body = Some(Vec::new());
+            } else if let Some(value) = trimmed.strip_prefix("title:") {
+                title = Some(value.trim());
+            }
+            continue;
+        };
+
+        if trimmed == "===" {
+            let (Some(title), Some(lines)) = (title.take(), body.take()) else {
+                return Err(Error::MalformedDocument);
+            };
+
+            nodes.push(YarnNode { title, lines });
+            continue;
+        }
+
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            continue;
+        }
+
+        lines.push(YarnLine {
+            number: index + 1,
+            indent: line.len() - line.trim_start().len(),
+            content: trimmed,
+        });
+    }
+
+    if body.is_some() {
+        return Err(Error::MalformedDocument);
+    }
+
+    Ok(nodes)
+}
+
+/// Reads the statements at or deeper than `indent`, starting at `pos`.
+fn block(
+    builder: &mut Builder,
+    lines: &[YarnLine],
+    pos: &mut usize,
+    indent: usize,
+) -> Vec<Statement> {
+    let mut statements = Vec::new();
+
+    while let Some(line) = lines.get(*pos) {
+        if line.indent < indent {
+            break;
+        }
+
+        *pos += 1;
+
+        if let Some(option) = line.content.strip_prefix("->") {
+            let text = strip_tags(builder, option, line.number);
+            let text = builder.text(&segments(text), line.number);
+            let body = block(builder, lines, pos, line.indent + 1);
+            let choice = Choice { text, body };
+
+            match statements.last_mut() {
+                Some(Statement::Choices(choices)) => choices.push(choice),
+                _ => statements.push(Statement::Choices(vec![choice])),
+            }
+            continue;
+        }
+
+        if let Some(command) = line
+            .content
+            .strip_prefix("<<")
+            .and_then(|command| command.strip_suffix(">>"))
+        {
+            let command = command.trim();
+
+            match command.split_once(char::is_whitespace) {
+                Some(("jump", target)) => statements.push(Statement::Jump {
+                    target: target.trim().to_string(),
+                    line: line.number,
+                }),
+                None if command == "stop" => statements.push(Statement::End),
+                _ => builder.warn(line.number, WarningKind::UnsupportedSyntax, line.content),
+            }
+            continue;
+        }
+
+        let content = strip_tags(builder, line.content, line.number);
+
+        let (speaker, text) = match split_speaker(content) {
+            Some((speaker, text)) => (Some(builder.text(&segments(speaker), line.number)), text),
+            None => (None, content),
+        };
+
+        statements.push(Statement::Line {
+            speaker,
+            text: builder.text(&segments(text), line.number),
+        });
+    }
+
+    statements
+}
+
+/// Removes the hashtags and the condition of an option from the end of a line.
+fn strip_tags<'s>(builder: &mut Builder, content: &'s str, line: usize) -> &'s str {
+    let (content, tags) = match content.find(" #") {
+        Some(index) => content.split_at(index),
+        None => (content, ""),
+    };
+
+    for tag in tags.split_whitespace() {
+        if !tag.starts_with("#line:") {
+            builder.warn(line, WarningKind::UnsupportedSyntax, tag);
+        }
+    }
+
+    match content.find("<<") {
+        Some(index) => {
+            builder.warn(line, WarningKind::UnsupportedSyntax, &content[index..]);
+            &content[..index]
+        }
+        None => content,
+    }
+}
+
+/// Splits the character name from a line like `Hunk: Hey!`.
+fn split_speaker(content: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+
+    for (index, c) in content.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '\\' | '[' => return None,
+            ':' if depth == 0 && index > 0 => {
+                return Some((&content[..index], &content[index + 1..]));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(index) = rest.find(['{', '[', '\\']) {
+        segments.push(Segment::Text(&rest[..index]));
+        rest = &rest[index..];
+
+        // Escaped characters are plain text
+        if rest.starts_with('\\') {
+            let escaped = rest[1..].chars().next().map_or(0, char::len_utf8);
+            segments.push(Segment::Text(&rest[1..1 + escaped]));
+            rest = &rest[1 + escaped..];
+            continue;
+        }
+
+        let closing = if rest.starts_with('{') { '}' } else { ']' };
+
+        let Some(end) = rest.find(closing) else {
+            break;
+        };
+
+        let (expression, remainder) = rest.split_at(end + 1);
+        let variable = expression
+            .strip_prefix("{$")
+            .and_then(|expression| expression.strip_suffix('}'))
+            .filter(|name| is_identifier(name));
+
+        segments.push(match variable {
+            Some(name) => Segment::Variable(name),
+            None => Segment::Unsupported(expression),
+        });
+        rest = remainder;
+    }
+
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, convert::ConversionWarning, verbs, DialogEvent, DialogMacroCompiler,
+        DialogRunner, NodeExit, RenderedLine,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    const SCRIPT: &str = r#"title: Start
+tags: intro
+---
+Hunk: Hey! Have you met {$pidge_name}?
+<<set $met to true>>
+-> Who?
+    Hunk: {$pidge_They} {$pidge_are} our engineer. #line:a1b2
+    <<jump Start>>
+-> Bye.
+    <<stop>>
+-> Tell me about [wave]work[/wave].
+Hunk: Ask {$pidge_her} yourself.
+===
+"#;
+
+    #[test]
+    fn yarn_test() -> Res {
+        let conversion = from_yarn(SCRIPT)?;
+        let document = conversion.document();
+
+        assert_eq!(document.nodes().len(), 3);
+
+        let NodeExit::Choices(choices) = document.node("Start").unwrap().exit() else {
+            panic!("expected choices");
+        };
+        assert_eq!(choices[0].target(), "Start~2");
+        assert_eq!(choices[1].target(), "END");
+        assert_eq!(choices[2].target(), "Start~1");
+
+        assert_eq!(
+            conversion
+                .warnings()
+                .iter()
+                .map(ConversionWarning::kind)
+                .collect::<Vec<_>>(),
+            [
+                WarningKind::UnsupportedSyntax,
+                WarningKind::UnsupportedSyntax,
+                WarningKind::UnsupportedSyntax,
+                WarningKind::AmbiguousPronoun,
+            ]
+        );
+        assert_eq!(conversion.warnings()[0].line(), 5);
+        assert_eq!(conversion.warnings()[1].source(), "[wave]");
+        assert_eq!(conversion.warnings()[3].line(), 12);
+
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict());
+        let mut runner = DialogRunner::new(&compiler, document);
+
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Hunk".to_string()),
+                text: "Hey! Have you met Pidge?".to_string(),
+            })
+        );
+        assert!(matches!(runner.advance()?, DialogEvent::Choices(_)));
+
+        runner.choose(0)?;
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Hunk".to_string()),
+                text: "They are our engineer.".to_string(),
+            })
+        );
+        assert_eq!(runner.current_node(), Some("Start~2"));
+
+        runner.advance()?;
+        assert_eq!(runner.current_node(), Some("Start"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn yarn_errors() {
+        assert!(matches!(
+            from_yarn("title: Start\n---\nHi"),
+            Err(Error::MalformedDocument)
+        ));
+
+        let conversion = from_yarn("title: Start\n---\n<<jump Nowhere>>\n===").unwrap();
+        assert_eq!(conversion.warnings()[0].kind(), WarningKind::UnknownTarget);
+        assert_eq!(conversion.document().nodes()[0].exit(), &NodeExit::End);
+    }
+}
@@ -1,12 +1,25 @@
-use serde::{Deserialize, Serialize};
-use smallvec::{smallvec, SmallVec};
+use std::{borrow::Cow, collections::HashMap, marker::PhantomData};
+
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
 
 use crate::{
-    character::{CharacterCast, Title},
-    verbs::Dictionary,
+    character::{CharacterCast, GrammaticalCharacter, Pronouns},
+    context::RenderContext,
+    grammar::{Case, Declension, English, Gender, Grammar, Inflection, PronounForm},
+    mods,
+    nouns::NounDictionary,
+    suggest,
+    template::{Segment, Template},
+    variables::Value,
+    verbs::{ConjugatePerson, Dictionary},
+    UnknownName,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum DialogMacroType {
     VerbConjugate,
     Name,
@@ -17,26 +30,383 @@ pub enum DialogMacroType {
     PossessivePronoun,
     ReflexivePronoun,
     PersonDescriptor,
+    Variable,
+    CountedNoun,
+    NameList,
+    /// "a" or "an", agreeing with the word given as data or else with the word that follows. With
+    /// a `character_id`, the article agrees with the character instead: "ein", "eine".
+    IndefiniteArticle,
+    /// "the", or in languages with gendered articles the one that agrees with the character.
+    DefiniteArticle,
+    /// The adjective given as data, inflected to agree with the character.
+    Adjective,
+    /// A macro type registered with `DialogMacroCompiler::with_macro`.
+    #[serde(untagged)]
+    Custom(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The names of the built-in macro types.
+pub(crate) const MACRO_TYPES: [&str; 15] = [
+    "VerbConjugate",
+    "Name",
+    "TitlePlusName",
+    "SubjectivePronoun",
+    "ObjectivePronoun",
+    "PossessiveDeterminer",
+    "PossessivePronoun",
+    "ReflexivePronoun",
+    "PersonDescriptor",
+    "Variable",
+    "CountedNoun",
+    "NameList",
+    "IndefiniteArticle",
+    "DefiniteArticle",
+    "Adjective",
+];
+
+/// The names of the built-in mods.
+pub(crate) const MACRO_MODS: [&str; 11] = [
+    "Capitalized",
+    "UpperCase",
+    "LowerCase",
+    "TitleCase",
+    "Possessive",
+    "Plural",
+    "Truncate",
+    "Stutter",
+    "Emphasized",
+    "WithIndefiniteArticle",
+    "NoAutoCapitalization",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum DialogMacroMod {
     Capitalized,
     UpperCase,
     LowerCase,
+    /// Uppercase the first letter of every word.
+    TitleCase,
+    /// Append the possessive suffix: "Pidge's", "Alfons'".
+    Possessive,
+    /// Use the plural of the output, e.g. of a `PersonDescriptor`.
+    Plural,
+    /// Keep at most the given number of characters.
+    Truncate(usize),
+    /// Repeat the start of the first word as a stutter: "Th-they".
+    Stutter,
+    /// Wrap the output in asterisks for emphasis: "*they*".
+    Emphasized,
+    /// Prefix the output with "a" or "an".
+    WithIndefiniteArticle,
+    /// Keep the output as is even if it starts a sentence and automatic capitalization is on.
+    NoAutoCapitalization,
+    /// A filter registered with `DialogMacroCompiler::with_mod`.
+    #[serde(untagged)]
+    Custom(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A macro as written in XYR text. `type_` and `mod` are accepted as aliases of `type` and `mods`,
+/// as is `_type`, the name of version 0. Unknown keys are rejected with a hint at the closest
+/// valid key.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(deny_unknown_fields))]
 pub struct DialogMacro<'a> {
-    character_id: &'a str,
+    #[cfg_attr(feature = "schema", schemars(default))]
+    character_id: Option<&'a str>,
+    #[serde(rename = "type")]
     _type: DialogMacroType,
+    #[cfg_attr(feature = "schema", schemars(default))]
     data: Option<&'a str>,
+    #[cfg_attr(feature = "schema", schemars(default))]
     mods: Vec<DialogMacroMod>,
+    /// Name of a numeric variable that nouns and verbs agree with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<&'a str>,
+    /// The grammatical case of pronouns, articles and adjectives in languages that decline them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    case: Option<Case>,
+    /// The gender of the noun a possessive determiner belongs to. A `count` makes it plural.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    noun_gender: Option<Gender>,
+    /// The declension of an `Adjective` in front of a noun.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declension: Option<Declension>,
+}
+
+impl<'a> DialogMacro<'a> {
+    pub fn new(
+        character_id: Option<&'a str>,
+        _type: DialogMacroType,
+        data: Option<&'a str>,
+        mods: Vec<DialogMacroMod>,
+    ) -> Self {
+        Self {
+            character_id,
+            _type,
+            data,
+            mods,
+            count: None,
+            case: None,
+            noun_gender: None,
+            declension: None,
+        }
+    }
+
+    /// Sets the name of the numeric variable that nouns and verbs agree with.
+    pub fn with_count(mut self, count: &'a str) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    pub fn with_case(mut self, case: Case) -> Self {
+        self.case = Some(case);
+        self
+    }
+
+    pub fn with_noun_gender(mut self, noun_gender: Gender) -> Self {
+        self.noun_gender = Some(noun_gender);
+        self
+    }
+
+    pub fn with_declension(mut self, declension: Declension) -> Self {
+        self.declension = Some(declension);
+        self
+    }
+
+    pub fn character_id(&self) -> Option<&'a str> {
+        self.character_id
+    }
+}
+
+/// The keys of a macro, in the order of `MacroKey`.
+const MACRO_KEYS: [&str; 8] = [
+    "character_id",
+    "type",
+    "data",
+    "mods",
+    "count",
+    "case",
+    "noun_gender",
+    "declension",
+];
+
+#[derive(Debug, Clone, Copy)]
+enum MacroKey {
+    CharacterId,
+    Type,
+    Data,
+    Mods,
+    Count,
+    Case,
+    NounGender,
+    Declension,
+}
+
+impl<'de> Deserialize<'de> for MacroKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl Visitor<'_> for KeyVisitor {
+            type Value = MacroKey;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a macro key")
+            }
+
+            fn visit_str<E: de::Error>(self, key: &str) -> Result<MacroKey, E> {
+                Ok(match key {
+                    "character_id" => MacroKey::CharacterId,
+                    "type" | "type_" | "_type" => MacroKey::Type,
+                    "data" => MacroKey::Data,
+                    "mods" | "mod" => MacroKey::Mods,
+                    "count" => MacroKey::Count,
+                    "case" => MacroKey::Case,
+                    "noun_gender" => MacroKey::NounGender,
+                    "declension" => MacroKey::Declension,
+                    _ => {
+                        return Err(match suggest::closest(key, MACRO_KEYS) {
+                            Some(closest) => E::custom(format_args!(
+                                "unknown macro key `{key}`, did you mean `{closest}`?"
+                            )),
+                            None => E::custom(format_args!("unknown macro key `{key}`")),
+                        })
+                    }
+                })
+            }
+        }
+
+        deserializer.deserialize_identifier(KeyVisitor)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for DialogMacro<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MacroVisitor<'a>(PhantomData<DialogMacro<'a>>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for MacroVisitor<'a> {
+            type Value = DialogMacro<'a>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a dialog macro")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut macr = DialogMacro::new(None, DialogMacroType::Name, None, Vec::new());
+                let mut seen = [false; MACRO_KEYS.len()];
+
+                while let Some(key) = map.next_key::<MacroKey>()? {
+                    // Also catches a key given in both spellings
+                    if std::mem::replace(&mut seen[key as usize], true) {
+                        return Err(de::Error::duplicate_field(MACRO_KEYS[key as usize]));
+                    }
+
+                    match key {
+                        MacroKey::CharacterId => macr.character_id = map.next_value()?,
+                        MacroKey::Type => macr._type = map.next_value()?,
+                        MacroKey::Data => macr.data = map.next_value()?,
+                        MacroKey::Mods => macr.mods = map.next_value()?,
+                        MacroKey::Count => macr.count = map.next_value()?,
+                        MacroKey::Case => macr.case = map.next_value()?,
+                        MacroKey::NounGender => macr.noun_gender = map.next_value()?,
+                        MacroKey::Declension => macr.declension = map.next_value()?,
+                    }
+                }
+
+                if !seen[MacroKey::Type as usize] {
+                    return Err(de::Error::missing_field("type"));
+                }
+
+                Ok(macr)
+            }
+        }
+
+        deserializer.deserialize_struct("DialogMacro", &MACRO_KEYS, MacroVisitor(PhantomData))
+    }
+}
+
+/// Renders a custom macro type.
+pub trait MacroHandler {
+    /// Renders the macro. `character` is the resolved character if the macro has a
+    /// `character_id`. The output still goes through the macro's mods.
+    fn render(
+        &self,
+        character: Option<&GrammaticalCharacter>,
+        data: Option<&str>,
+        ctx: &RenderContext,
+    ) -> Result<String, crate::Error>;
+
+    /// Renders the macro in the language of the compiler's grammar. Handlers whose output
+    /// depends on the language override this, the default ignores the grammar.
+    fn render_in(
+        &self,
+        _grammar: &dyn Grammar,
+        character: Option<&GrammaticalCharacter>,
+        data: Option<&str>,
+        ctx: &RenderContext,
+    ) -> Result<String, crate::Error> {
+        self.render(character, data, ctx)
+    }
+}
+
+impl<F> MacroHandler for F
+where
+    F: Fn(
+        Option<&GrammaticalCharacter>,
+        Option<&str>,
+        &RenderContext,
+    ) -> Result<String, crate::Error>,
+{
+    fn render(
+        &self,
+        character: Option<&GrammaticalCharacter>,
+        data: Option<&str>,
+        ctx: &RenderContext,
+    ) -> Result<String, crate::Error> {
+        self(character, data, ctx)
+    }
+}
+
+/// A custom output filter that can be referenced by name in the mods of a macro.
+pub trait TextFilter {
+    fn apply(&self, input: String) -> String;
+}
+
+impl<F> TextFilter for F
+where
+    F: Fn(String) -> String,
+{
+    fn apply(&self, input: String) -> String {
+        self(input)
+    }
+}
+
+/// What the compiler renders in place of a macro that fails with an error of some kind, see
+/// `DialogMacroCompiler::with_fallback`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Fallback {
+    /// Return the error.
+    #[default]
+    Fail,
+    /// Render the given text instead of the macro.
+    Placeholder(String),
+    /// Render the macro itself, as written in the dialog.
+    RawMacro,
+    /// Render as if the missing data was neutral: unknown characters are they/them and named after
+    /// their identifier, unknown verbs and persons use the infinitive, unknown variables and macro
+    /// types render nothing, unknown count variables count one, and unknown mods leave the text
+    /// unchanged.
+    Neutral,
+}
+
+/// The kinds of errors a `Fallback` can be set for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FallbackKind {
+    /// `UnknownCharacterIdentifier`, also for members of groups.
+    Character,
+    /// `UnknownVerbKey` and `UndefinedVerbCase`.
+    Verb,
+    /// `UnknownVariable`.
+    Variable,
+    /// `UnknownMacroType`.
+    MacroType,
+    /// `UnknownMod`.
+    Mod,
+}
+
+impl FallbackKind {
+    pub const ALL: [FallbackKind; 5] = [
+        FallbackKind::Character,
+        FallbackKind::Verb,
+        FallbackKind::Variable,
+        FallbackKind::MacroType,
+        FallbackKind::Mod,
+    ];
+
+    fn of(error: &crate::Error) -> Option<Self> {
+        match error {
+            crate::Error::UnknownCharacterIdentifier(_) => Some(FallbackKind::Character),
+            crate::Error::UnknownVerbKey(_) | crate::Error::UndefinedVerbCase => {
+                Some(FallbackKind::Verb)
+            }
+            crate::Error::UnknownVariable => Some(FallbackKind::Variable),
+            crate::Error::UnknownMacroType(_) => Some(FallbackKind::MacroType),
+            crate::Error::UnknownMod(_) => Some(FallbackKind::Mod),
+            _ => None,
+        }
+    }
 }
 
 pub struct DialogMacroCompiler<'a> {
     cast: CharacterCast,
     dictionary: Dictionary<'a>,
+    nouns: NounDictionary<'a>,
+    grammar: Box<dyn Grammar + 'a>,
+    auto_capitalize: bool,
+    macro_handlers: HashMap<String, Box<dyn MacroHandler + 'a>>,
+    filters: HashMap<String, Box<dyn TextFilter + 'a>>,
+    fallbacks: HashMap<FallbackKind, Fallback>,
 }
 
 impl<'a> DialogMacroCompiler<'a> {
@@ -44,108 +414,267 @@ impl<'a> DialogMacroCompiler<'a> {
         Self {
             cast,
             dictionary: dict,
+            nouns: NounDictionary::default(),
+            grammar: Box::new(English),
+            auto_capitalize: false,
+            macro_handlers: HashMap::new(),
+            filters: HashMap::new(),
+            fallbacks: HashMap::new(),
         }
     }
 
-    pub fn parse_and_compile(&self, src: &str) -> Result<String, crate::Error> {
-        let mut output = String::new();
+    /// Use the given table of irregular nouns for `CountedNoun` macros.
+    pub fn with_nouns(mut self, nouns: NounDictionary<'a>) -> Self {
+        self.nouns = nouns;
+        self
+    }
 
-        let mut src_slice = src;
+    /// Render macros using the rules of the given language instead of English.
+    pub fn with_grammar(mut self, grammar: impl Grammar + 'a) -> Self {
+        self.grammar = Box::new(grammar);
+        self
+    }
 
-        if src_slice.is_empty() {
-            return Ok("".to_string());
-        }
+    /// Capitalize macros that start a sentence in `parse_and_compile`, i.e. macros at the start
+    /// of a line or after `.`, `!` or `?`, including any opening quotes in between.
+    pub fn with_auto_capitalization(mut self, enabled: bool) -> Self {
+        self.auto_capitalize = enabled;
+        self
+    }
 
-        while !src_slice.is_empty() {
-            // We expect there to be a next slice, otherwise src_slice should be empty!
-            let mut split = src_slice.split('{');
-            let mut next_slice = split.next().unwrap();
+    /// Registers a handler for macros whose `type` is `name`. Built-in macro types take
+    /// precedence over custom ones with the same name.
+    pub fn with_macro(mut self, name: impl Into<String>, handler: impl MacroHandler + 'a) -> Self {
+        self.macro_handlers.insert(name.into(), Box::new(handler));
+        self
+    }
 
-            // Check that any '}' is properly escaped
-            if let Some(index) = next_slice.find('}') {
-                // Brace was properly escaped
-                if !matches!(next_slice.get(index..index + 2), Some("}}")) {
-                    return Err(crate::Error::UnmatchedClosingBrace);
-                }
+    /// Registers a filter that is applied where `name` appears in the mods of a macro. Built-in
+    /// mods take precedence over custom ones with the same name.
+    pub fn with_mod(mut self, name: impl Into<String>, filter: impl TextFilter + 'a) -> Self {
+        self.filters.insert(name.into(), Box::new(filter));
+        self
+    }
 
-                output.push_str(&next_slice[..index + 1]);
-                src_slice = &src_slice[next_slice[..index + 2].as_bytes().len()..];
-                next_slice = &next_slice[index + 2..];
-            }
+    /// Renders `fallback` instead of failing on errors of the given kind. Errors of every kind
+    /// fail by default.
+    pub fn with_fallback(mut self, kind: FallbackKind, fallback: Fallback) -> Self {
+        self.fallbacks.insert(kind, fallback);
+        self
+    }
+
+    /// Renders `fallback` instead of failing on errors of any kind.
+    pub fn with_fallbacks(mut self, fallback: Fallback) -> Self {
+        for kind in FallbackKind::ALL {
+            self.fallbacks.insert(kind, fallback.clone());
+        }
+        self
+    }
 
-            // Push the slice onto the output string and shrink src_slice
-            output.push_str(next_slice);
-            src_slice = &src_slice[next_slice.as_bytes().len()..];
+    pub fn parse_and_compile(&self, src: &str) -> Result<String, crate::Error> {
+        self.parse_and_compile_with(src, &RenderContext::default())
+    }
 
-            let Some(next) = split.next() else {
-                break;
-            };
+    pub fn parse_and_compile_with(
+        &self,
+        src: &str,
+        ctx: &RenderContext,
+    ) -> Result<String, crate::Error> {
+        self.compile_template_with(&Template::parse(src)?, ctx)
+    }
 
-            // If the next slice is empty, this means the string was "{{"
-            if next.is_empty() {
-                output.push('{');
-                src_slice = &src_slice["{{".as_bytes().len()..];
-            } else {
-                let macro_str = self.delimit_macro(src_slice);
+    pub fn compile_template(&self, template: &Template) -> Result<String, crate::Error> {
+        self.compile_template_with(template, &RenderContext::default())
+    }
 
-                src_slice = &src_slice[macro_str.as_bytes().len()..];
+    pub fn compile_template_with(
+        &self,
+        template: &Template,
+        ctx: &RenderContext,
+    ) -> Result<String, crate::Error> {
+        let mut output = String::new();
 
-                let macro_: DialogMacro = serde_json::from_str(macro_str)?;
+        // An article that waits for the next word to be rendered
+        let mut pending_article: Option<PendingArticle> = None;
+
+        for segment in template.segments() {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Macro { macr, source } => {
+                    let capitalize = self.auto_capitalize
+                        && !macr.mods.contains(&DialogMacroMod::NoAutoCapitalization)
+                        && pending_article.is_none()
+                        && starts_sentence(&output);
+
+                    // Articles without data agree with the next word, which isn't rendered yet
+                    if macr._type == DialogMacroType::IndefiniteArticle
+                        && macr.data.is_none()
+                        && macr.character_id.is_none()
+                    {
+                        if pending_article.is_some() {
+                            return Err(crate::Error::MissingArticleNoun);
+                        }
+
+                        pending_article = Some(PendingArticle {
+                            position: output.len(),
+                            mods: macr.mods.clone(),
+                            capitalize,
+                        });
+                        continue;
+                    }
+
+                    let mut value = self
+                        .render(macr, ctx)
+                        .or_else(|error| self.fallback(error, source))?;
+
+                    if capitalize {
+                        value = mods::capitalize(&value);
+                    }
+
+                    output.push_str(&value);
+                }
+            }
 
-                let value = self.compile(macro_)?;
+            if let Some(article) = pending_article.take() {
+                pending_article = article.insert(&mut output, self)?;
+            }
+        }
 
-                output.push_str(&value);
+        if let Some(article) = pending_article.take() {
+            if article.insert(&mut output, self)?.is_some() {
+                return Err(crate::Error::MissingArticleNoun);
             }
         }
 
         Ok(output)
     }
 
-    fn delimit_macro<'b>(&self, src: &'b str) -> &'b str {
-        assert!(src.starts_with('{'));
+    pub fn compile(&self, macr: DialogMacro<'a>) -> Result<String, crate::Error> {
+        self.compile_with(macr, &RenderContext::default())
+    }
 
-        let mut counter = 0;
+    pub fn compile_with(
+        &self,
+        macr: DialogMacro,
+        ctx: &RenderContext,
+    ) -> Result<String, crate::Error> {
+        self.render(&macr, ctx)
+            .or_else(|error| self.fallback(error, &serde_json::to_string(&macr)?))
+    }
 
-        let mut bytes = 0;
+    /// The fallback for errors of the given kind.
+    fn fallback_for(&self, kind: FallbackKind) -> &Fallback {
+        self.fallbacks.get(&kind).unwrap_or(&Fallback::Fail)
+    }
 
-        let mut in_string = false;
-        let mut escape = false;
+    /// Whether missing data of the given kind is replaced with neutral data while rendering.
+    fn is_neutral(&self, kind: FallbackKind) -> bool {
+        self.fallback_for(kind) == &Fallback::Neutral
+    }
 
-        for c in src.chars() {
-            match c {
-                '{' if !in_string => {
-                    counter += 1;
-                }
-                '}' if !in_string => {
-                    counter -= 1;
-                }
-                '\\' if in_string => {
-                    escape = !escape;
-                }
-                '"' if !in_string => {
-                    in_string = true;
-                }
-                '"' if in_string && !escape => {
-                    in_string = false;
+    /// Renders the fallback for a macro that failed with `error`, or returns the error.
+    fn fallback(&self, error: crate::Error, raw_macro: &str) -> Result<String, crate::Error> {
+        let Some(kind) = FallbackKind::of(&error) else {
+            return Err(error);
+        };
+
+        match self.fallback_for(kind) {
+            Fallback::Placeholder(placeholder) => Ok(placeholder.clone()),
+            Fallback::RawMacro => Ok(raw_macro.to_string()),
+            // Neutral data is filled in while rendering, so what's left are errors it can't fix
+            Fallback::Fail | Fallback::Neutral => Err(error),
+        }
+    }
+
+    fn render(&self, macr: &DialogMacro, ctx: &RenderContext) -> Result<String, crate::Error> {
+        if let DialogMacroType::Custom(name) = &macr._type {
+            let Some(handler) = self.macro_handlers.get(name) else {
+                if self.is_neutral(FallbackKind::MacroType) {
+                    return Ok(String::new());
                 }
-                _ => {
-                    escape = false;
+
+                return Err(self.unknown_macro_type(name));
+            };
+
+            let subject = match macr.character_id {
+                Some(character_id) => Some((character_id, self.resolve(character_id, ctx)?)),
+                None => None,
+            };
+
+            let person = subject.as_ref().map(|(character_id, subject)| {
+                self.grammatical_character(character_id, subject, ctx)
+            });
+
+            let raw_string =
+                handler.render_in(self.grammar.as_ref(), person.as_deref(), macr.data, ctx)?;
+
+            return self.apply_mods(raw_string, &macr.mods);
+        }
+
+        // Macros that do not refer to any character
+        let characterless = match macr._type {
+            DialogMacroType::Variable => {
+                let Some(name) = macr.data else {
+                    return Err(crate::Error::MissingMacroData);
+                };
+
+                match Self::variable(name, ctx) {
+                    Err(crate::Error::UnknownVariable)
+                        if self.is_neutral(FallbackKind::Variable) =>
+                    {
+                        Some(String::new())
+                    }
+                    value => Some(value?.to_string()),
                 }
             }
+            DialogMacroType::CountedNoun => {
+                let Some(noun) = macr.data else {
+                    return Err(crate::Error::MissingMacroData);
+                };
+
+                Some(
+                    self.nouns
+                        .inflect(noun, self.count(macr, ctx)?, self.grammar.as_ref()),
+                )
+            }
+            DialogMacroType::IndefiniteArticle if macr.character_id.is_none() => {
+                let Some(word) = macr.data else {
+                    return Err(crate::Error::MissingMacroData);
+                };
+
+                Some(self.grammar.indefinite_article(word))
+            }
+            DialogMacroType::VerbConjugate if macr.count.is_some() => {
+                let Some(data) = macr.data else {
+                    return Err(crate::Error::MissingMacroData);
+                };
 
-            bytes += c.len_utf8();
+                let person = self.grammar.count_person(self.count(macr, ctx)?);
 
-            if counter == 0 {
-                break;
+                Some(self.conjugate(data, person)?)
             }
+            _ => None,
+        };
+
+        if let Some(raw_string) = characterless {
+            return self.apply_mods(raw_string, &macr.mods);
         }
 
-        &src[0..bytes]
-    }
+        let Some(character_id) = macr.character_id else {
+            return Err(crate::Error::MissingCharacterIdentifier);
+        };
 
-    pub fn compile(&self, macr: DialogMacro<'a>) -> Result<String, crate::Error> {
-        let Some(person) = self.cast.get(macr.character_id) else {
-            return Err(crate::Error::UnknownCharacterIdentifier);
+        let subject = self.resolve(character_id, ctx)?;
+        let person = self.grammatical_character(character_id, &subject, ctx);
+
+        let inflection = Inflection {
+            case: macr.case,
+            noun_gender: macr.noun_gender,
+            noun_plural: match macr.count {
+                Some(_) => !self.grammar.is_singular(self.count(macr, ctx)?),
+                None => false,
+            },
+            declension: macr.declension,
         };
 
         // TODO: there's probably a bit too much logic in this function that should be put somewhere else
@@ -156,228 +685,1310 @@ impl<'a> DialogMacroCompiler<'a> {
                     return Err(crate::Error::MissingMacroData);
                 };
 
-                self.dictionary.conjugate(data, person.conjugate_case())?
+                let conjugate_person = self.grammar.conjugate_person(&person);
+
+                self.conjugate(data, conjugate_person)?
             }
             DialogMacroType::Name => person.name().to_string(),
-            DialogMacroType::TitlePlusName => match person.title() {
-                Some(title) if !matches!(title, &Title::NoTitle) => {
-                    format!("{} {}", title.str(), person.name())
+            DialogMacroType::NameList => {
+                let names: Vec<String> = match &subject {
+                    Subject::Character(person) => vec![person.name().to_string()],
+                    Subject::Group(members) => members
+                        .iter()
+                        .map(|member| member.name().to_string())
+                        .collect(),
+                };
+
+                self.grammar.join_list(&names, macr.data)
+            }
+            DialogMacroType::TitlePlusName => match &subject {
+                Subject::Character(person) => self.grammar.title_plus_name(person),
+                Subject::Group(members) => {
+                    let names: Vec<String> = members
+                        .iter()
+                        .map(|member| self.grammar.title_plus_name(member))
+                        .collect();
+
+                    self.grammar.join_list(&names, None)
                 }
-                _ => person.name().to_string(),
             },
-            DialogMacroType::SubjectivePronoun => person.subjective_pronoun(),
-            DialogMacroType::ObjectivePronoun => person.objective_pronoun(),
-            DialogMacroType::PossessiveDeterminer => person.possessive_determiner(),
-            DialogMacroType::PossessivePronoun => person.possessive_pronoun(),
-            DialogMacroType::ReflexivePronoun => person.reflexive_pronoun(),
-            DialogMacroType::PersonDescriptor => {
-                if let Some(descriptor) = person.person_descriptor() {
-                    descriptor.to_string()
-                } else {
-                    "person".to_string()
-                }
+            DialogMacroType::SubjectivePronoun => {
+                self.grammar
+                    .pronoun(&person, PronounForm::Subjective, &inflection)
+            }
+            DialogMacroType::ObjectivePronoun => {
+                self.grammar
+                    .pronoun(&person, PronounForm::Objective, &inflection)
+            }
+            DialogMacroType::PossessiveDeterminer => {
+                self.grammar
+                    .pronoun(&person, PronounForm::PossessiveDeterminer, &inflection)
+            }
+            DialogMacroType::PossessivePronoun => {
+                self.grammar
+                    .pronoun(&person, PronounForm::Possessive, &inflection)
+            }
+            DialogMacroType::ReflexivePronoun => {
+                self.grammar
+                    .pronoun(&person, PronounForm::Reflexive, &inflection)
+            }
+            DialogMacroType::PersonDescriptor => self.grammar.person_descriptor(&person),
+            DialogMacroType::IndefiniteArticle => self.grammar.article(&person, false, &inflection),
+            DialogMacroType::DefiniteArticle => self.grammar.article(&person, true, &inflection),
+            DialogMacroType::Adjective => {
+                let Some(stem) = macr.data else {
+                    return Err(crate::Error::MissingMacroData);
+                };
+
+                self.grammar.adjective(&person, stem, &inflection)
+            }
+            DialogMacroType::Variable
+            | DialogMacroType::CountedNoun
+            | DialogMacroType::Custom(_) => {
+                unreachable!("characterless macros are compiled above")
             }
         };
 
-        Ok(apply_mods(raw_string, &macr.mods))
+        self.apply_mods(raw_string, &macr.mods)
     }
-}
 
-// TODO: this could probably be moved to its own file
-fn apply_mods(mut input: String, mods: &[DialogMacroMod]) -> String {
-    for _mod in mods {
-        match _mod {
-            DialogMacroMod::Capitalized => {
-                let mut first = true;
+    /// Looks up a character, or else a group from the render context or the cast, in the locale
+    /// of the grammar.
+    fn resolve(&self, key: &str, ctx: &RenderContext) -> Result<Subject<'_>, crate::Error> {
+        let locale = self.grammar.locale();
 
-                input = input
-                    .chars()
-                    .flat_map(|x| {
-                        // Unicode to_uppercase may turn one character into multiple ones. For this reason
-                        // we need to provide a vector for the ToUppercase iterator to write into.
-                        // Think e.g. ß -> SS.
-                        // Use SmallVec to avoid heap allocations.
-                        let y: SmallVec<[char; 2]> = if first {
-                            x.to_uppercase().collect()
-                        } else {
-                            smallvec![x]
-                        };
+        let get = |key: &str| {
+            let mut person = self.cast.get_localized(key, locale)?;
 
-                        first = false;
+            // Overridden pronouns also replace the gender the old ones suggested
+            if let Some(pronouns) = ctx.pronouns(key) {
+                let person = person.to_mut();
 
-                        y
-                    })
-                    .collect();
-            }
-            DialogMacroMod::UpperCase => {
-                input = input.to_uppercase();
-            }
-            DialogMacroMod::LowerCase => {
-                input = input.to_lowercase();
+                person.set_pronouns(pronouns.clone());
+                person.set_gender(None);
             }
+
+            Some(person)
+        };
+
+        if let Some(person) = get(key) {
+            return Ok(Subject::Character(person));
         }
-    }
 
-    input
-}
+        let unknown = |id: &str| {
+            if self.is_neutral(FallbackKind::Character) {
+                let neutral =
+                    GrammaticalCharacter::new(id.to_string(), Pronouns::TheyThem, None, None);
+                return Ok(Cow::Owned(neutral));
+            }
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use crate::{character, verbs};
+            Err(self.unknown_character(id, ctx))
+        };
 
-    use super::*;
+        let Some(member_ids) = self.group_members(key, ctx) else {
+            return unknown(key).map(Subject::Character);
+        };
 
-    type Res = Result<(), crate::Error>;
+        let members = member_ids
+            .iter()
+            .map(|id| get(id).map_or_else(|| unknown(id), Ok))
+            .collect::<Result<_, _>>()?;
 
-    fn gen_compiler() -> DialogMacroCompiler<'static> {
-        DialogMacroCompiler {
-            cast: character::tests::gen_cast(),
-            dictionary: verbs::tests::gen_dict(),
-        }
+        Ok(Subject::Group(members))
     }
 
-    #[test]
-    fn test_mods() -> Res {
-        // DialogMacroMod::Capitalized
-        assert_eq!(
-            apply_mods("fooBar".to_string(), &[DialogMacroMod::Capitalized]),
-            "FooBar"
-        );
+    /// The character that renders the macros of a subject, taking groups and the perspective into
+    /// account.
+    fn grammatical_character<'s>(
+        &self,
+        key: &str,
+        subject: &'s Subject,
+        ctx: &RenderContext,
+    ) -> Cow<'s, GrammaticalCharacter> {
+        let mut person = match subject {
+            Subject::Character(person) => Cow::Borrowed(person.as_ref()),
+            Subject::Group(members) => {
+                let members: Vec<&GrammaticalCharacter> =
+                    members.iter().map(|member| member.as_ref()).collect();
+
+                Cow::Owned(self.grammar.group(&members))
+            }
+        };
 
-        // DialogMacroMod::UpperCase
-        assert_eq!(
-            apply_mods("fooBar".to_string(), &[DialogMacroMod::UpperCase]),
-            "FOOBAR"
-        );
+        // The speaker and the person spoken to use first and second person forms, but keep their
+        // gender for agreement
+        if let Some(perspective) = self.perspective(key, ctx) {
+            let gender = self.grammar.gender(&person);
+            let person = person.to_mut();
 
-        // DialogMacroMod::LowerCase
-        assert_eq!(
-            apply_mods("fooBar".to_string(), &[DialogMacroMod::LowerCase]),
-            "foobar"
-        );
+            person.set_gender(Some(gender));
+            person.set_perspective(Some(perspective));
+        }
 
-        // Chaining
-        assert_eq!(
-            apply_mods(
-                "fooBar".to_string(),
-                &[DialogMacroMod::LowerCase, DialogMacroMod::Capitalized]
-            ),
-            "Foobar"
-        );
+        person
+    }
 
-        Ok(())
+    fn group_members<'s>(&'s self, key: &str, ctx: &'s RenderContext) -> Option<&'s [String]> {
+        ctx.group(key).or_else(|| self.cast.group(key))
     }
 
-    #[test]
-    fn print_macro() -> Res {
-        let dm = DialogMacro {
-            character_id: "pidge",
-            _type: DialogMacroType::SubjectivePronoun,
-            data: None,
-            mods: vec![],
-        };
+    /// The grammatical person a character or group is referred to in. Groups that include the
+    /// speaker are "we", groups that include the person spoken to are "you".
+    fn perspective(&self, key: &str, ctx: &RenderContext) -> Option<ConjugatePerson> {
+        let is_first = |id: &str| ctx.first_person() == Some(id);
+        let is_second = |id: &str| ctx.second_person() == Some(id);
 
-        assert_eq!(
-            serde_json::to_string(&dm)?,
-            r#"{"character_id":"pidge","_type":"SubjectivePronoun","data":null,"mods":[]}"#
-        );
+        if is_first(key) {
+            return Some(ConjugatePerson::FirstSingular);
+        }
 
-        let verb_dm = DialogMacro {
-            character_id: "pidge",
-            _type: DialogMacroType::VerbConjugate,
-            data: Some("to be"),
-            mods: vec![],
-        };
+        if is_second(key) {
+            return Some(ConjugatePerson::SecondSingular);
+        }
 
-        assert_eq!(
-            serde_json::to_string(&verb_dm)?,
-            r#"{"character_id":"pidge","_type":"VerbConjugate","data":"to be","mods":[]}"#
-        );
+        let members = self.group_members(key, ctx)?;
 
-        Ok(())
+        if members.iter().any(|id| is_first(id)) {
+            Some(ConjugatePerson::FirstPlural)
+        } else if members.iter().any(|id| is_second(id)) {
+            Some(ConjugatePerson::SecondPlural)
+        } else {
+            None
+        }
     }
 
-    #[test]
-    fn compile_test() -> Res {
-        let pidge_possessive = DialogMacro {
-            character_id: "pidge",
-            _type: DialogMacroType::PossessivePronoun,
-            data: None,
-            mods: vec![],
-        };
-
-        let tupo_objective = DialogMacro {
-            character_id: "tupo",
-            _type: DialogMacroType::ObjectivePronoun,
-            data: None,
-            mods: vec![DialogMacroMod::Capitalized],
-        };
+    fn apply_mods(
+        &self,
+        mut input: String,
+        mods: &[DialogMacroMod],
+    ) -> Result<String, crate::Error> {
+        for _mod in mods {
+            input = match _mod {
+                DialogMacroMod::Capitalized => mods::capitalize(&input),
+                DialogMacroMod::UpperCase => input.to_uppercase(),
+                DialogMacroMod::LowerCase => input.to_lowercase(),
+                DialogMacroMod::TitleCase => mods::title_case(&input),
+                DialogMacroMod::Possessive => self.grammar.possessive(&input),
+                DialogMacroMod::Plural => self.nouns.plural(&input, self.grammar.as_ref()),
+                DialogMacroMod::Truncate(len) => mods::truncate(&input, *len),
+                DialogMacroMod::Stutter => mods::stutter(&input),
+                DialogMacroMod::Emphasized => format!("*{input}*"),
+                DialogMacroMod::WithIndefiniteArticle => {
+                    format!("{} {input}", self.grammar.indefinite_article(&input))
+                }
+                DialogMacroMod::NoAutoCapitalization => input,
+                DialogMacroMod::Custom(name) => {
+                    let Some(filter) = self.filters.get(name) else {
+                        if self.is_neutral(FallbackKind::Mod) {
+                            continue;
+                        }
 
-        let compiler = gen_compiler();
+                        return Err(self.unknown_mod(name));
+                    };
 
-        assert_eq!(compiler.compile(pidge_possessive)?, "theirs");
+                    filter.apply(input)
+                }
+            };
+        }
 
-        assert_eq!(compiler.compile(tupo_objective)?, "Xem");
+        Ok(input)
+    }
 
-        Ok(())
+    /// Checks that a macro only refers to characters, groups, verbs, macro types and mods the
+    /// compiler knows, without rendering it. Groups and variables of a render context are not
+    /// known here, so only the cast is searched for characters.
+    pub fn check(&self, macr: &DialogMacro) -> Result<(), crate::Error> {
+        self.check_names(macr)?;
+        self.check_character(macr)?;
+        self.check_verb(macr)
     }
 
-    #[test]
-    fn full_compiler_test() -> Res {
-        let source = r#"Do you know {"character_id":"pidge","_type":"Name","data":null,"mods":[]}? {"character_id":"pidge","_type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"pidge","_type":"VerbConjugate","data":"to be","mods":[]} super smart! I love {"character_id":"pidge","_type":"ObjectivePronoun","data":null,"mods":[]}! Have you seen {"character_id":"pidge","_type":"PossessiveDeterminer","data":null,"mods":[]} sentient robot?"#;
-        let expected = "Do you know Pidge? They are super smart! I love them! Have you seen their sentient robot?";
+    /// Checks the macro type and mods, which are independent of the cast and dictionary.
+    pub fn check_names(&self, macr: &DialogMacro) -> Result<(), crate::Error> {
+        if let DialogMacroType::Custom(name) = &macr._type {
+            if !self.macro_handlers.contains_key(name) {
+                return Err(self.unknown_macro_type(name));
+            }
+        }
 
-        let compiler = gen_compiler();
+        for _mod in &macr.mods {
+            if let DialogMacroMod::Custom(name) = _mod {
+                if !self.filters.contains_key(name) {
+                    return Err(self.unknown_mod(name));
+                }
+            }
+        }
 
-        let output = compiler.parse_and_compile(source)?;
+        Ok(())
+    }
 
-        assert_eq!(&output, expected,);
+    /// Checks that the character of a macro is in the cast, as a character or a group.
+    pub fn check_character(&self, macr: &DialogMacro) -> Result<(), crate::Error> {
+        if let Some(id) = macr.character_id {
+            if self.cast.get(id).is_none() && self.cast.group(id).is_none() {
+                return Err(self.unknown_character(id, &RenderContext::default()));
+            }
+        }
 
         Ok(())
     }
 
-    #[test]
-    fn error_tests() -> Res {
-        let unknown_verb = r#"{"character_id":"pidge","_type":"VerbConjugate","data":"to be or not to be","mods":[]}"#;
-        let unknown_character =
-            r#"{"character_id":"edward elrich","_type":"VerbConjugate","data":"to be","mods":[]}"#;
-        let null_character =
-            r#"{"character_id":null,"_type":"VerbConjugate","data":"to be","mods":[]}"#;
-        let unknown_type = r#"{"character_id":"pidge","_type":"Alchemy","data":null,"mods":[]}"#;
-        let unknown_mod = r#"{"character_id":"pidge","_type":"VerbConjugate","data":"to be","mods":["Supercalifragilisticexpialidocious"]}"#;
+    /// Checks that the verb of a `VerbConjugate` macro is in the dictionary, whatever its
+    /// character.
+    pub fn check_verb(&self, macr: &DialogMacro) -> Result<(), crate::Error> {
+        if let (DialogMacroType::VerbConjugate, Some(verb)) = (&macr._type, macr.data) {
+            if !self.dictionary.keys().any(|key| key == verb) {
+                return Err(crate::Error::UnknownVerbKey(UnknownName::new(
+                    verb,
+                    self.dictionary.keys(),
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unknown_character(&self, id: &str, ctx: &RenderContext) -> crate::Error {
+        let known = self
+            .cast
+            .keys()
+            .chain(self.cast.group_keys())
+            .chain(ctx.group_keys());
+
+        crate::Error::UnknownCharacterIdentifier(UnknownName::new(id, known))
+    }
+
+    fn unknown_macro_type(&self, name: &str) -> crate::Error {
+        let known = MACRO_TYPES
+            .into_iter()
+            .chain(self.macro_handlers.keys().map(String::as_str));
+
+        crate::Error::UnknownMacroType(UnknownName::new(name, known))
+    }
+
+    fn unknown_mod(&self, name: &str) -> crate::Error {
+        let known = MACRO_MODS
+            .into_iter()
+            .chain(self.filters.keys().map(String::as_str));
+
+        crate::Error::UnknownMod(UnknownName::new(name, known))
+    }
+
+    /// Conjugates a verb, falling back to its infinitive if verbs are neutral.
+    fn conjugate(&self, verb: &str, person: ConjugatePerson) -> Result<String, crate::Error> {
+        match self.grammar.conjugate(&self.dictionary, verb, person) {
+            Err(crate::Error::UnknownVerbKey(_) | crate::Error::UndefinedVerbCase)
+                if self.is_neutral(FallbackKind::Verb) =>
+            {
+                let infinitive = self.dictionary.infinitive(verb);
+                Ok(infinitive
+                    .unwrap_or_else(|| verb.strip_prefix("to ").unwrap_or(verb))
+                    .to_string())
+            }
+            conjugated => conjugated,
+        }
+    }
+
+    fn variable(name: &str, ctx: &RenderContext) -> Result<Value, crate::Error> {
+        ctx.variables()
+            .and_then(|variables| variables.get(name))
+            .ok_or(crate::Error::UnknownVariable)
+    }
+
+    /// Looks up the numeric variable named by the `count` attribute of the macro.
+    /// The value of the count variable of a macro, or one if the variable is unknown and variables
+    /// fall back to neutral data.
+    fn count(&self, macr: &DialogMacro, ctx: &RenderContext) -> Result<f64, crate::Error> {
+        let Some(name) = macr.count else {
+            return Err(crate::Error::MissingMacroCount);
+        };
+
+        match Self::variable(name, ctx) {
+            Err(crate::Error::UnknownVariable) if self.is_neutral(FallbackKind::Variable) => {
+                Ok(1.0)
+            }
+            value => value?.as_f64().ok_or(crate::Error::NonNumericCount),
+        }
+    }
+}
+
+struct PendingArticle {
+    /// Byte offset in the output where the article goes.
+    position: usize,
+    mods: Vec<DialogMacroMod>,
+    capitalize: bool,
+}
+
+impl PendingArticle {
+    /// Inserts the article in front of the next word if that word has been rendered already.
+    /// Otherwise the article is handed back.
+    fn insert(
+        self,
+        output: &mut String,
+        compiler: &DialogMacroCompiler,
+    ) -> Result<Option<Self>, crate::Error> {
+        let following = &output[self.position..];
+
+        // Wait for the first letter or digit, the word may start with a quote or similar
+        if !following.contains(char::is_alphanumeric) {
+            return Ok(Some(self));
+        }
+
+        let mut article =
+            compiler.apply_mods(compiler.grammar.indefinite_article(following), &self.mods)?;
+
+        if self.capitalize {
+            article = mods::capitalize(&article);
+        }
+
+        output.insert_str(self.position, &article);
+
+        Ok(None)
+    }
+}
+
+/// The character or group of characters a macro refers to.
+enum Subject<'c> {
+    Character(Cow<'c, GrammaticalCharacter>),
+    Group(Vec<Cow<'c, GrammaticalCharacter>>),
+}
+
+/// Returns the macro at the start of `src`, up to and including its closing brace.
+pub(crate) fn delimit_macro(src: &str) -> &str {
+    assert!(src.starts_with('{'));
+
+    let mut counter = 0;
+
+    let mut bytes = 0;
+
+    let mut in_string = false;
+    let mut escape = false;
+
+    for c in src.chars() {
+        match c {
+            '{' if !in_string => {
+                counter += 1;
+            }
+            '}' if !in_string => {
+                counter -= 1;
+            }
+            '\\' if in_string => {
+                escape = !escape;
+            }
+            '"' if !in_string => {
+                in_string = true;
+            }
+            '"' if in_string && !escape => {
+                in_string = false;
+            }
+            _ => {
+                escape = false;
+            }
+        }
+
+        bytes += c.len_utf8();
+
+        if counter == 0 {
+            break;
+        }
+    }
+
+    &src[0..bytes]
+}
+
+/// Whether text appended to `preceding` starts a new sentence, i.e. it is at the start of a line,
+/// after an opening quote, or after `.`, `!` or `?`.
+fn starts_sentence(preceding: &str) -> bool {
+    let is_opening = |c: char| matches!(c, '“' | '‘' | '«' | '„' | '¿' | '¡');
+    let is_straight_quote = |c: char| matches!(c, '"' | '\'');
+
+    let trimmed = preceding.trim_end();
+
+    // Start of the text or of a line
+    if trimmed.is_empty() || preceding[trimmed.len()..].contains('\n') {
+        return true;
+    }
+
+    let mut chars = trimmed.chars().rev();
+    let last = chars.next().unwrap();
+    let before_last = chars.next();
+
+    // Straight quotes only open a quote if they don't directly follow a word, e.g. `"Hi!" they`
+    let opens_quote = is_opening(last)
+        || (is_straight_quote(last)
+            && before_last
+                .is_none_or(|c| !c.is_alphanumeric() && !matches!(c, '.' | '!' | '?' | ',')));
+
+    opens_quote || matches!(last, '.' | '!' | '?')
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        character::{self, CharacterLocalization, Title},
+        nouns,
+        variables::Value,
+        verbs::{self, Verb},
+    };
+
+    use super::*;
+
+    type Res = Result<(), crate::Error>;
+
+    fn gen_compiler() -> DialogMacroCompiler<'static> {
+        DialogMacroCompiler {
+            cast: character::tests::gen_cast(),
+            dictionary: verbs::tests::gen_dict(),
+            nouns: nouns::tests::gen_nouns(),
+            grammar: Box::new(English),
+            auto_capitalize: false,
+            macro_handlers: HashMap::new(),
+            filters: HashMap::new(),
+            fallbacks: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_mods() -> Res {
+        let compiler = gen_compiler();
+
+        // DialogMacroMod::Capitalized
+        assert_eq!(
+            compiler.apply_mods("fooBar".to_string(), &[DialogMacroMod::Capitalized])?,
+            "FooBar"
+        );
+
+        // DialogMacroMod::UpperCase
+        assert_eq!(
+            compiler.apply_mods("fooBar".to_string(), &[DialogMacroMod::UpperCase])?,
+            "FOOBAR"
+        );
+
+        // DialogMacroMod::LowerCase
+        assert_eq!(
+            compiler.apply_mods("fooBar".to_string(), &[DialogMacroMod::LowerCase])?,
+            "foobar"
+        );
+
+        // Chaining
+        assert_eq!(
+            compiler.apply_mods(
+                "fooBar".to_string(),
+                &[DialogMacroMod::LowerCase, DialogMacroMod::Capitalized]
+            )?,
+            "Foobar"
+        );
+
+        // DialogMacroMod::TitleCase
+        assert_eq!(
+            compiler.apply_mods(
+                "king of the lions".to_string(),
+                &[DialogMacroMod::TitleCase]
+            )?,
+            "King Of The Lions"
+        );
+
+        // DialogMacroMod::Possessive
+        assert_eq!(
+            compiler.apply_mods("Alfons".to_string(), &[DialogMacroMod::Possessive])?,
+            "Alfons'"
+        );
+
+        // DialogMacroMod::Plural
+        assert_eq!(
+            compiler.apply_mods("Man".to_string(), &[DialogMacroMod::Plural])?,
+            "Men"
+        );
+        assert_eq!(
+            compiler.apply_mods("thief".to_string(), &[DialogMacroMod::Plural])?,
+            "thieves"
+        );
+
+        // DialogMacroMod::Truncate
+        assert_eq!(
+            compiler.apply_mods("Straße".to_string(), &[DialogMacroMod::Truncate(5)])?,
+            "Straß"
+        );
+
+        // DialogMacroMod::Stutter
+        assert_eq!(
+            compiler.apply_mods(
+                "they".to_string(),
+                &[DialogMacroMod::Stutter, DialogMacroMod::Capitalized]
+            )?,
+            "Th-they"
+        );
+
+        // DialogMacroMod::Emphasized
+        assert_eq!(
+            compiler.apply_mods("xem".to_string(), &[DialogMacroMod::Emphasized])?,
+            "*xem*"
+        );
+
+        // Mods with parameters in macros
+        let truncated = r#"{"character_id":"alfons","type":"Name","data":null,"mods":[{"Truncate":3},"UpperCase"]}"#;
+
+        assert_eq!(compiler.parse_and_compile(truncated)?, "ALF");
+
+        Ok(())
+    }
+
+    #[test]
+    fn print_macro() -> Res {
+        let dm = DialogMacro {
+            character_id: Some("pidge"),
+            _type: DialogMacroType::SubjectivePronoun,
+            data: None,
+            mods: vec![],
+            count: None,
+            case: None,
+            noun_gender: None,
+            declension: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&dm)?,
+            r#"{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":[]}"#
+        );
+
+        let verb_dm = DialogMacro {
+            character_id: Some("pidge"),
+            _type: DialogMacroType::VerbConjugate,
+            data: Some("to be"),
+            mods: vec![],
+            count: None,
+            case: None,
+            noun_gender: None,
+            declension: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&verb_dm)?,
+            r#"{"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":[]}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn compile_test() -> Res {
+        let pidge_possessive = DialogMacro {
+            character_id: Some("pidge"),
+            _type: DialogMacroType::PossessivePronoun,
+            data: None,
+            mods: vec![],
+            count: None,
+            case: None,
+            noun_gender: None,
+            declension: None,
+        };
+
+        let tupo_objective = DialogMacro {
+            character_id: Some("tupo"),
+            _type: DialogMacroType::ObjectivePronoun,
+            data: None,
+            mods: vec![DialogMacroMod::Capitalized],
+            count: None,
+            case: None,
+            noun_gender: None,
+            declension: None,
+        };
+
+        let compiler = gen_compiler();
+
+        assert_eq!(compiler.compile(pidge_possessive)?, "theirs");
+
+        assert_eq!(compiler.compile(tupo_objective)?, "Xem");
+
+        Ok(())
+    }
+
+    #[test]
+    fn full_compiler_test() -> Res {
+        let source = r#"Do you know {"character_id":"pidge","type":"Name","data":null,"mods":[]}? {"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":[]} super smart! I love {"character_id":"pidge","type":"ObjectivePronoun","data":null,"mods":[]}! Have you seen {"character_id":"pidge","type":"PossessiveDeterminer","data":null,"mods":[]} sentient robot?"#;
+        let expected = "Do you know Pidge? They are super smart! I love them! Have you seen their sentient robot?";
+
+        let compiler = gen_compiler();
+
+        let output = compiler.parse_and_compile(source)?;
+
+        assert_eq!(&output, expected,);
+
+        Ok(())
+    }
+
+    #[test]
+    fn macro_keys_test() -> Res {
+        let compiler = gen_compiler();
+
+        // The spellings from the README, without data and mods
+        assert_eq!(
+            compiler.parse_and_compile(
+                r#"{"character_id":"pidge","type_":"SubjectivePronoun","mod":["Capitalized"]} {"character_id":"pidge","type":"VerbConjugate","data":"to be"} here."#
+            )?,
+            "They are here."
+        );
+
+        // The key of version 0
+        assert_eq!(
+            compiler.parse_and_compile(r#"{"character_id":"pidge","_type":"Name"}"#)?,
+            "Pidge"
+        );
+
+        let error = |source: &str| match compiler.parse_and_compile(source) {
+            Err(crate::Error::Serde(error)) => error.to_string(),
+            result => panic!("expected a serde error, got {result:?}"),
+        };
+
+        assert!(error(r#"{"character_id":"pidge","typ":"Name"}"#)
+            .starts_with("unknown macro key `typ`, did you mean `type`?"));
+        assert!(error(r#"{"character":"pidge","type":"Name"}"#)
+            .starts_with("unknown macro key `character`, did you mean `character_id`?"));
+        assert!(error(r#"{"type":"Name","pronouns":"HeHim"}"#)
+            .starts_with("unknown macro key `pronouns`"));
+        assert!(error(r#"{"type":"Name","type_":"Name"}"#).starts_with("duplicate field `type`"));
+        assert!(error(r#"{"character_id":"pidge"}"#).starts_with("missing field `type`"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn error_tests() -> Res {
+        let unknown_verb = r#"{"character_id":"pidge","type":"VerbConjugate","data":"to be or not to be","mods":[]}"#;
+        let unknown_character =
+            r#"{"character_id":"edward elrich","type":"VerbConjugate","data":"to be","mods":[]}"#;
+        let null_character =
+            r#"{"character_id":null,"type":"VerbConjugate","data":"to be","mods":[]}"#;
+        let unknown_type = r#"{"character_id":"pidge","type":"Alchemy","data":null,"mods":[]}"#;
+        let unknown_mod = r#"{"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":["Supercalifragilisticexpialidocious"]}"#;
 
         let compiler = gen_compiler();
 
         // Unknown Verb
         assert!(matches!(
             compiler.parse_and_compile(unknown_verb),
-            Err(crate::Error::UnknownVerbKey),
+            Err(crate::Error::UnknownVerbKey(_)),
         ));
 
         // Unknown Character
         assert!(matches!(
             compiler.parse_and_compile(unknown_character),
-            Err(crate::Error::UnknownCharacterIdentifier),
+            Err(crate::Error::UnknownCharacterIdentifier(_)),
         ));
 
         // Null Character
         assert!(matches!(
             compiler.parse_and_compile(null_character),
-            Err(crate::Error::Serde(_)),
+            Err(crate::Error::MissingCharacterIdentifier),
         ));
 
         // Unknown Type
         assert!(matches!(
             compiler.parse_and_compile(unknown_type),
-            Err(crate::Error::Serde(_)),
+            Err(crate::Error::UnknownMacroType(_)),
         ));
 
         // Unknown Mod
         assert!(matches!(
             compiler.parse_and_compile(unknown_mod),
-            Err(crate::Error::Serde(_)),
+            Err(crate::Error::UnknownMod(_)),
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn suggestion_test() -> Res {
+        let compiler = gen_compiler().with_mod("Pirate", |input: String| input);
+
+        let error = |source: &str| compiler.parse_and_compile(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error(r#"{"character_id":"pigde","type":"Name"}"#),
+            "unknown character identifier `pigde`, did you mean `pidge`?"
+        );
+        assert_eq!(
+            error(r#"{"character_id":"pidge","type":"VerbConjugate","data":"to bee"}"#),
+            "unknown verb key `to bee`, did you mean `to be`?"
+        );
+        assert_eq!(
+            error(r#"{"character_id":"pidge","type":"Nmae"}"#),
+            "unknown macro type `Nmae`, did you mean `Name`?"
+        );
+        assert_eq!(
+            error(r#"{"character_id":"pidge","type":"Name","mods":["Capitalised"]}"#),
+            "unknown macro mod `Capitalised`, did you mean `Capitalized`?"
+        );
+        assert_eq!(
+            error(r#"{"character_id":"pidge","type":"Name","mods":["Priate"]}"#),
+            "unknown macro mod `Priate`, did you mean `Pirate`?"
+        );
+        assert_eq!(
+            error(r#"{"character_id":"pidge","type":"Alchemy"}"#),
+            "unknown macro type `Alchemy`"
+        );
+
+        // Members of a group are checked too
+        let ctx = RenderContext::new().with_group("team".to_string(), vec!["hnuk".to_string()]);
+        let Err(crate::Error::UnknownCharacterIdentifier(id)) =
+            compiler.parse_and_compile_with(r#"{"character_id":"team","type":"Name"}"#, &ctx)
+        else {
+            panic!("expected an unknown character");
+        };
+        assert_eq!(id.name(), "hnuk");
+        assert_eq!(id.suggestion(), Some("hunk"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn fallback_test() -> Res {
+        let unknown_character =
+            r#"{"character_id":"keith","type":"SubjectivePronoun","mod":["Capitalized"]}"#;
+        let unknown_verb = r#"{"character_id":"keith","type":"VerbConjugate","data":"to fly"}"#;
+        let unknown_variable = r#"{"type":"Variable","data":"gold"}"#;
+        let unknown_type = r#"{"character_id":"pidge","type":"Alchemy"}"#;
+        let unknown_mod = r#"{"character_id":"pidge","type":"Name","mods":["Sparkle"]}"#;
+        let source = format!(
+            "{unknown_character} {unknown_verb}{unknown_variable}{unknown_type} with {unknown_mod}."
+        );
+
+        // Strict by default
+        assert!(matches!(
+            gen_compiler().parse_and_compile(&source),
+            Err(crate::Error::UnknownCharacterIdentifier(_))
+        ));
+
+        let compiler = gen_compiler().with_fallbacks(Fallback::Neutral);
+        assert_eq!(compiler.parse_and_compile(&source)?, "They fly with Pidge.");
+
+        // Unknown count variables count one
+        assert_eq!(
+            compiler.parse_and_compile(
+                r#"{"type":"CountedNoun","data":"coin","count":"gold"} {"type":"VerbConjugate","data":"to be","count":"gold"} {"character_id":"pidge","type":"IndefiniteArticle","count":"gold"} gift"#
+            )?,
+            "coin is a gift"
+        );
+
+        let compiler = gen_compiler()
+            .with_fallbacks(Fallback::Placeholder("???".to_string()))
+            .with_fallback(FallbackKind::Variable, Fallback::RawMacro)
+            .with_fallback(FallbackKind::Mod, Fallback::Fail);
+        assert_eq!(
+            compiler.parse_and_compile(&format!(
+                "{unknown_character} {unknown_verb}{unknown_variable}"
+            ))?,
+            format!("??? ???{unknown_variable}")
+        );
+        assert!(matches!(
+            compiler.parse_and_compile(unknown_mod),
+            Err(crate::Error::UnknownMod(_))
+        ));
+
+        // Errors without a fallback kind still fail
+        assert!(matches!(
+            compiler.parse_and_compile(r#"{"type":"Name"}"#),
+            Err(crate::Error::MissingCharacterIdentifier)
+        ));
+
+        // Neutral verbs use the infinitive of known verbs in persons they don't define
+        let mut dictionary = Dictionary::default();
+        dictionary.insert(
+            "to go".to_string(),
+            Verb::new("to go", Some("go"), None, None, None, None, None, None),
+        );
+        let compiler = DialogMacroCompiler::new(character::tests::gen_cast(), dictionary)
+            .with_fallback(FallbackKind::Verb, Fallback::Neutral);
+        assert_eq!(
+            compiler.parse_and_compile(
+                r#"{"character_id":"hunk","type":"VerbConjugate","data":"to go"}"#
+            )?,
+            "go"
+        );
+
+        // Compiling a single macro renders it as JSON
+        let compiler = gen_compiler().with_fallback(FallbackKind::MacroType, Fallback::RawMacro);
+        let macr: DialogMacro = serde_json::from_str(unknown_type)?;
+        assert_eq!(
+            compiler.compile(macr)?,
+            r#"{"character_id":"pidge","type":"Alchemy","data":null,"mods":[]}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_test() -> Res {
+        let compiler = gen_compiler().with_mod("Pirate", |input: String| input);
+        let check = |source: &str| compiler.check(&serde_json::from_str(source)?);
+
+        check(
+            r#"{"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":["Pirate"]}"#,
+        )?;
+        check(r#"{"type":"Variable","data":"gold"}"#)?;
+
+        assert!(matches!(
+            check(r#"{"character_id":"pigde","type":"Name"}"#),
+            Err(crate::Error::UnknownCharacterIdentifier(_))
+        ));
+        assert!(matches!(
+            check(r#"{"character_id":"pidge","type":"VerbConjugate","data":"to bee"}"#),
+            Err(crate::Error::UnknownVerbKey(_))
+        ));
+        assert!(matches!(
+            compiler.check_verb(&serde_json::from_str(
+                r#"{"character_id":"pigde","type":"VerbConjugate","data":"to bee"}"#
+            )?),
+            Err(crate::Error::UnknownVerbKey(_))
+        ));
+        assert!(matches!(
+            check(r#"{"character_id":"pidge","type":"Nmae"}"#),
+            Err(crate::Error::UnknownMacroType(_))
+        ));
+        assert!(matches!(
+            check(r#"{"character_id":"pidge","type":"Name","mods":["Priate"]}"#),
+            Err(crate::Error::UnknownMod(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn builtin_names_test() -> Res {
+        for name in MACRO_TYPES {
+            let _type: DialogMacroType = serde_json::from_value(name.into())?;
+            assert!(!matches!(_type, DialogMacroType::Custom(_)), "{name}");
+        }
+
+        for name in MACRO_MODS.into_iter().filter(|&name| name != "Truncate") {
+            let _mod: DialogMacroMod = serde_json::from_value(name.into())?;
+            assert!(!matches!(_mod, DialogMacroMod::Custom(_)), "{name}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn variable_test() -> Res {
+        let compiler = gen_compiler();
+
+        let mut variables = HashMap::new();
+        variables.insert("gold".to_string(), Value::from(12));
+        variables.insert("item".to_string(), Value::from("bayard"));
+
+        let ctx = RenderContext::new().with_variables(&variables);
+
+        let source = r#"You have {"type":"Variable","data":"gold","mods":[]} coins and {"character_id":"pidge","type":"PossessiveDeterminer","data":null,"mods":[]} {"type":"Variable","data":"item","mods":["Capitalized"]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "You have 12 coins and their Bayard."
+        );
+
+        // Unknown variables
+        let unknown = r#"{"type":"Variable","data":"quest","mods":[]}"#;
+
+        assert!(matches!(
+            compiler.parse_and_compile_with(unknown, &ctx),
+            Err(crate::Error::UnknownVariable),
+        ));
+
+        // No variables at all
+        assert!(matches!(
+            compiler.parse_and_compile(source),
+            Err(crate::Error::UnknownVariable),
         ));
 
+        // Variable without a name
+        let nameless = r#"{"type":"Variable","data":null,"mods":[]}"#;
+
+        assert!(matches!(
+            compiler.parse_and_compile_with(nameless, &ctx),
+            Err(crate::Error::MissingMacroData),
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn count_agreement_test() -> Res {
+        let compiler = gen_compiler();
+
+        let source = r#"{"type":"Variable","data":"n","mods":[]} {"type":"CountedNoun","data":"thief","mods":[],"count":"n"} {"type":"VerbConjugate","data":"to be","mods":[],"count":"n"} here."#;
+
+        let mut variables = HashMap::new();
+
+        for (n, expected) in [
+            (1, "1 thief is here."),
+            (3, "3 thieves are here."),
+            (0, "0 thieves are here."),
+        ] {
+            variables.insert("n".to_string(), Value::from(n));
+            let ctx = RenderContext::new().with_variables(&variables);
+
+            assert_eq!(compiler.parse_and_compile_with(source, &ctx)?, expected);
+        }
+
+        // Regular plural rules
+        let wolves = r#"{"type":"CountedNoun","data":"wolf","mods":["Capitalized"],"count":"n"}"#;
+        let ctx = RenderContext::new().with_variables(&variables);
+
+        assert_eq!(compiler.parse_and_compile_with(wolves, &ctx)?, "Wolves");
+
+        // Missing count
+        let missing_count = r#"{"type":"CountedNoun","data":"wolf","mods":[]}"#;
+
+        assert!(matches!(
+            compiler.parse_and_compile_with(missing_count, &ctx),
+            Err(crate::Error::MissingMacroCount),
+        ));
+
+        // Count that is not a number
+        variables.insert("n".to_string(), Value::from("many"));
+        let ctx = RenderContext::new().with_variables(&variables);
+
+        assert!(matches!(
+            compiler.parse_and_compile_with(wolves, &ctx),
+            Err(crate::Error::NonNumericCount),
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn group_test() -> Res {
+        let mut compiler = gen_compiler();
+        compiler.cast.insert_group(
+            "paladins".to_string(),
+            vec!["pidge".to_string(), "hunk".to_string()],
+        );
+
+        let source = r#"{"character_id":"paladins","type":"NameList","data":null,"mods":[]} {"character_id":"paladins","type":"VerbConjugate","data":"to be","mods":[]} here; {"character_id":"paladins","type":"SubjectivePronoun","data":null,"mods":[]} brought {"character_id":"paladins","type":"PossessiveDeterminer","data":null,"mods":[]} lions."#;
+
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "Pidge and Hunk are here; they brought their lions."
+        );
+
+        // Groups built at render time
+        let ctx = RenderContext::new().with_group(
+            "paladins".to_string(),
+            vec![
+                "pidge".to_string(),
+                "hunk".to_string(),
+                "alfons".to_string(),
+            ],
+        );
+
+        let either = r#"{"character_id":"paladins","type":"NameList","data":"or","mods":[]}"#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(either, &ctx)?,
+            "Pidge, Hunk or Alfons"
+        );
+
+        let titled = r#"{"character_id":"paladins","type":"TitlePlusName","data":null,"mods":[]}"#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(titled, &ctx)?,
+            "Pidge, Mr. Hunk and King Alfons"
+        );
+
+        // Groups with unknown members
+        let ctx = RenderContext::new().with_group(
+            "paladins".to_string(),
+            vec!["pidge".to_string(), "edward elrich".to_string()],
+        );
+
+        assert!(matches!(
+            compiler.parse_and_compile_with(either, &ctx),
+            Err(crate::Error::UnknownCharacterIdentifier(_)),
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn perspective_test() -> Res {
+        let mut compiler = gen_compiler();
+        compiler.cast.insert_group(
+            "paladins".to_string(),
+            vec!["pidge".to_string(), "hunk".to_string()],
+        );
+
+        let ctx = RenderContext::new()
+            .with_first_person("hunk".to_string())
+            .with_second_person("pidge".to_string());
+
+        let source = r#"{"character_id":"hunk","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"hunk","type":"VerbConjugate","data":"to be","mods":[]} sure {"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":[]} {"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":[]} proud of {"character_id":"pidge","type":"PossessiveDeterminer","data":null,"mods":[]} sword and {"character_id":"pidge","type":"ReflexivePronoun","data":null,"mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "I am sure you are proud of your sword and yourself."
+        );
+
+        // Without a perspective the same macros use the third person
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "He is sure they are proud of their sword and themself."
+        );
+
+        // Groups including the speaker or the person spoken to
+        let group = r#"{"character_id":"paladins","type":"SubjectivePronoun","data":null,"mods":[]} {"character_id":"paladins","type":"VerbConjugate","data":"to be","mods":[]} {"character_id":"paladins","type":"ObjectivePronoun","data":null,"mods":[]}"#;
+
+        assert_eq!(compiler.parse_and_compile_with(group, &ctx)?, "we are us");
+
+        let ctx = RenderContext::new().with_second_person("hunk".to_string());
+
+        assert_eq!(compiler.parse_and_compile_with(group, &ctx)?, "you are you");
+
+        Ok(())
+    }
+
+    #[test]
+    fn auto_capitalization_test() -> Res {
+        let compiler = gen_compiler().with_auto_capitalization(true);
+
+        let they = r#"{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":[]}"#;
+
+        for (source, expected) in [
+            (format!("{they} left."), "They left."),
+            (format!("Hey! {they} left."), "Hey! They left."),
+            (format!("Who? {they}?"), "Who? They?"),
+            (format!("Hi.\n{they} left."), "Hi.\nThey left."),
+            (format!("Hi. \"{they} left.\""), "Hi. \"They left.\""),
+            (format!("He said “{they} left.”"), "He said “They left.”"),
+            (
+                format!("He said \"{they} left.\""),
+                "He said \"They left.\"",
+            ),
+            (format!("I know {they} left."), "I know they left."),
+            (format!("\"Hi!\" {they} said."), "\"Hi!\" they said."),
+        ] {
+            assert_eq!(compiler.parse_and_compile(&source)?, expected);
+        }
+
+        // Suppressed by the mod
+        let suppressed = r#"{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":["NoAutoCapitalization"]} left."#;
+
+        assert_eq!(compiler.parse_and_compile(suppressed)?, "they left.");
+
+        // Off by default
+        assert_eq!(
+            gen_compiler().parse_and_compile(&format!("{they} left."))?,
+            "they left."
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn article_test() -> Res {
+        let compiler = gen_compiler().with_auto_capitalization(true);
+
+        let mut variables = HashMap::new();
+        let article = r#"{"type":"IndefiniteArticle","data":null,"mods":[]}"#;
+        let item = r#"{"type":"Variable","data":"item","mods":[]}"#;
+
+        for (word, expected) in [
+            ("elf", "You see an elf."),
+            ("wolf", "You see a wolf."),
+            ("hour", "You see an hour."),
+            ("unicorn", "You see a unicorn."),
+            ("European", "You see a European."),
+        ] {
+            variables.insert("item".to_string(), Value::from(word));
+            let ctx = RenderContext::new().with_variables(&variables);
+
+            let source = format!("You see {article} {item}.");
+
+            assert_eq!(compiler.parse_and_compile_with(&source, &ctx)?, expected);
+        }
+
+        // Across macros and at the start of a sentence
+        let tupo = r#"{"type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"tupo","type":"PersonDescriptor","data":null,"mods":["LowerCase"]} and {"type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"alfons","type":"Name","data":null,"mods":[]}."#;
+
+        assert_eq!(compiler.parse_and_compile(tupo)?, "A laru and an Alfons.");
+
+        // Literal text
+        let literal = r#"{"type":"IndefiniteArticle","data":null,"mods":[]} orc"#;
+
+        assert_eq!(compiler.parse_and_compile(literal)?, "An orc");
+
+        // Data and mod
+        let data = r#"{"type":"IndefiniteArticle","data":"honest","mods":["UpperCase"]}"#;
+
+        assert_eq!(compiler.parse_and_compile(data)?, "AN");
+
+        let with_article = r#"Meet {"character_id":"alfons","type":"PersonDescriptor","data":null,"mods":["LowerCase","WithIndefiniteArticle"]}."#;
+
+        assert_eq!(compiler.parse_and_compile(with_article)?, "Meet a man.");
+
+        // Quotes in between
+        let quoted =
+            format!(r#"You see {article} "{{"type":"Variable","data":"item","mods":[]}}"."#);
+        let ctx = RenderContext::new().with_variables(&variables);
+
+        assert_eq!(
+            compiler.parse_and_compile_with(&quoted, &ctx)?,
+            "You see a \"European\"."
+        );
+
+        // Nothing follows the article
+        assert!(matches!(
+            compiler.parse_and_compile(&format!("You see {article}.")),
+            Err(crate::Error::MissingArticleNoun),
+        ));
+
+        Ok(())
+    }
+
+    struct Species;
+
+    impl MacroHandler for Species {
+        fn render(
+            &self,
+            character: Option<&GrammaticalCharacter>,
+            _data: Option<&str>,
+            _ctx: &RenderContext,
+        ) -> Result<String, crate::Error> {
+            let Some(character) = character else {
+                return Err(crate::Error::MissingCharacterIdentifier);
+            };
+
+            Ok(match character.name() {
+                "Tupo" => "laru",
+                _ => "human",
+            }
+            .to_string())
+        }
+    }
+
+    #[test]
+    fn custom_macro_test() -> Res {
+        let ranks = HashMap::from([("hunk", "Paladin"), ("pidge", "Cadet")]);
+
+        let compiler = gen_compiler()
+            .with_macro("Species", Species)
+            .with_macro(
+                "ShipName",
+                |_: Option<&GrammaticalCharacter>, data: Option<&str>, ctx: &RenderContext| {
+                    let Some(variable) = data else {
+                        return Err(crate::Error::MissingMacroData);
+                    };
+
+                    let name = ctx
+                        .variables()
+                        .and_then(|variables| variables.get(variable));
+
+                    Ok(format!(
+                        "the {}",
+                        name.ok_or(crate::Error::UnknownVariable)?
+                    ))
+                },
+            )
+            .with_macro(
+                "Rank",
+                move |character: Option<&GrammaticalCharacter>,
+                      _: Option<&str>,
+                      _: &RenderContext| {
+                    Ok(ranks
+                        .get(character.map_or("", |c| c.name()).to_lowercase().as_str())
+                        .unwrap_or(&"Civilian")
+                        .to_string())
+                },
+            );
+
+        let mut variables = HashMap::new();
+        variables.insert("ship".to_string(), Value::from("castle of lions"));
+        let ctx = RenderContext::new().with_variables(&variables);
+
+        let source = r#"{"character_id":"tupo","type":"Species","data":null,"mods":["Capitalized"]}, {"character_id":"pidge","type":"Species","data":null,"mods":["WithIndefiniteArticle"]} and {"character_id":"hunk","type":"Rank","data":null,"mods":["UpperCase"]} board {"type":"ShipName","data":"ship","mods":["TitleCase"]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "Laru, a human and PALADIN board The Castle Of Lions."
+        );
+
+        // Handlers may fail
+        let no_character = r#"{"type":"Species","data":null,"mods":[]}"#;
+
+        assert!(matches!(
+            compiler.parse_and_compile(no_character),
+            Err(crate::Error::MissingCharacterIdentifier),
+        ));
+
+        // Unregistered types
+        let unregistered = r#"{"character_id":"tupo","type":"Faction","data":null,"mods":[]}"#;
+
+        assert!(matches!(
+            compiler.parse_and_compile(unregistered),
+            Err(crate::Error::UnknownMacroType(_)),
+        ));
+
+        Ok(())
+    }
+
+    struct Transliterate;
+
+    impl TextFilter for Transliterate {
+        fn apply(&self, input: String) -> String {
+            input
+                .chars()
+                .map(|c| match c.to_ascii_lowercase() {
+                    'a' | 'e' | 'i' | 'o' | 'u' => 'ö',
+                    _ => c,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn custom_mod_test() -> Res {
+        let compiler = gen_compiler()
+            .with_mod("Alien", Transliterate)
+            .with_mod("Pirate", |input: String| input.replace("you", "ye"));
+
+        let source = r#"{"character_id":"alfons","type":"Name","data":null,"mods":["Alien","UpperCase"]} greets {"character_id":"pidge","type":"Name","data":null,"mods":["LowerCase","Alien","Capitalized"]}."#;
+
+        assert_eq!(compiler.parse_and_compile(source)?, "ÖLFÖNS greets Pödgö.");
+
+        let ctx = RenderContext::new().with_second_person("hunk".to_string());
+        let pirate = r#"Ahoy, {"character_id":"hunk","type":"ObjectivePronoun","data":null,"mods":["Pirate"]}!"#;
+
+        assert_eq!(compiler.parse_and_compile_with(pirate, &ctx)?, "Ahoy, ye!");
+
+        Ok(())
+    }
+
+    #[test]
+    fn localization_test() -> Res {
+        let mut cast = character::tests::gen_cast();
+        cast.insert_localization(
+            "es".to_string(),
+            "pidge".to_string(),
+            CharacterLocalization::new()
+                .with_title(Title::Custom("Señore".to_string()))
+                .with_person_descriptor("ingeniere".to_string()),
+        );
+        cast.insert_localization(
+            "es".to_string(),
+            "hunk".to_string(),
+            CharacterLocalization::new().with_person_descriptor("hombre".to_string()),
+        );
+
+        let compiler = DialogMacroCompiler::new(cast, verbs::tests::gen_dict())
+            .with_grammar(crate::Spanish::new());
+
+        let source = r#"{"character_id":"pidge","type":"TitlePlusName","data":null,"mods":[]} es {"character_id":"pidge","type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"pidge","type":"PersonDescriptor","data":null,"mods":[]}. {"character_id":"hunk","type":"TitlePlusName","data":null,"mods":[]} es {"character_id":"hunk","type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"hunk","type":"PersonDescriptor","data":null,"mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "Señore Pidge es une ingeniere. Sr. Hunk es un hombre."
+        );
+
         Ok(())
     }
 
@@ -392,7 +2003,7 @@ pub(crate) mod tests {
             "Do you ever just public static void main(String[] args) {}?"
         );
 
-        let mixed_escaped_and_macro = r#"{{{"character_id":"pidge","_type":"ObjectivePronoun","data":null,"mods":["UpperCase"]}}}"#;
+        let mixed_escaped_and_macro = r#"{{{"character_id":"pidge","type":"ObjectivePronoun","data":null,"mods":["UpperCase"]}}}"#;
 
         assert_eq!(
             compiler.parse_and_compile(mixed_escaped_and_macro)?,
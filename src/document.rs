@@ -0,0 +1,667 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crate::Error;
+
+/// Label of the implicit node that holds every line before the first node header.
+pub const START_LABEL: &str = "start";
+
+/// Jump target that ends the conversation.
+pub const END_LABEL: &str = "END";
+
+/// Marks a line as a dialog line, even if it starts like a node header, choice, jump or comment.
+const ESCAPE: char = '\\';
+
+/// The starts of lines that aren't dialog lines unless escaped.
+const SYNTAX_PREFIXES: [&str; 5] = ["===", "*", "->", "//", "\\"];
+
+/// A branching XYR document.
+///
+/// A document is a list of labeled nodes. Every node holds a number of dialog lines and ends in
+/// an exit: a jump to another node, a set of choices for the player, an end marker, or nothing at
+/// all, in which case the conversation falls through to the next node in the document. A dialog
+/// line may start with a speaker template, separated from the spoken text by a tab character.
+/// A line that starts with `\` is always a dialog line, the backslash is removed. Text without a
+/// speaker that contains a tab is written as `\`, a tab and the text.
+///
+/// ```text
+/// === start ===
+/// Hey there!
+/// * Who are you? -> intro
+/// * Bye. -> END
+///
+/// === intro ===
+/// I'm new here.
+/// -> start
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct XyrDocument {
+    nodes: Vec<DialogNode>,
+    labels: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DialogNode {
+    label: String,
+    lines: Vec<DialogLine>,
+    exit: NodeExit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogLine {
+    speaker: Option<String>,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogChoice {
+    text: String,
+    target: String,
+}
+
+/// Identifies a translatable text of a document: a dialog line or a choice of a node.
+///
+/// IDs are written as `label.3` for the fourth line of a node and `label.choice.0` for its first
+/// choice. Labels can't contain dots, so every ID names one text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextId {
+    Line { node: String, index: usize },
+    Choice { node: String, index: usize },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NodeExit {
+    /// Continue with the next node in the document, or end if this is the last node.
+    #[default]
+    FallThrough,
+    Jump(String),
+    Choices(Vec<DialogChoice>),
+    End,
+}
+
+impl XyrDocument {
+    pub fn parse(src: &str) -> Result<Self, Error> {
+        let mut document = Self::default();
+        let mut current: Option<DialogNode> = None;
+
+        for line in src.lines() {
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+
+            // Escaped dialog lines skip the syntax below
+            let escaped = line.trim_start().strip_prefix(ESCAPE);
+
+            // Node header
+            if let (None, Some(header)) = (escaped, trimmed.strip_prefix("===")) {
+                let label = header.trim_end_matches('=').trim();
+
+                if !is_valid_label(label) {
+                    return Err(Error::MalformedDocument);
+                }
+
+                if let Some(node) = current.take() {
+                    document.push_node(node)?;
+                }
+
+                current = Some(DialogNode::new(label.to_string()));
+                continue;
+            }
+
+            let node = current.get_or_insert_with(|| DialogNode::new(START_LABEL.to_string()));
+
+            // Choice
+            if let (None, Some(choice)) = (escaped, trimmed.strip_prefix('*')) {
+                let Some((text, target)) = choice.rsplit_once("->") else {
+                    return Err(Error::MalformedDocument);
+                };
+
+                let choice = DialogChoice {
+                    text: text.trim().to_string(),
+                    target: parse_target(target)?,
+                };
+
+                match &mut node.exit {
+                    NodeExit::FallThrough => node.exit = NodeExit::Choices(vec![choice]),
+                    NodeExit::Choices(choices) => choices.push(choice),
+                    NodeExit::Jump(_) | NodeExit::End => return Err(Error::MalformedDocument),
+                }
+                continue;
+            }
+
+            // Jump or end marker
+            if let (None, Some(target)) = (escaped, trimmed.strip_prefix("->")) {
+                if !matches!(node.exit, NodeExit::FallThrough) {
+                    return Err(Error::MalformedDocument);
+                }
+
+                let target = parse_target(target)?;
+
+                node.exit = if target == END_LABEL {
+                    NodeExit::End
+                } else {
+                    NodeExit::Jump(target)
+                };
+                continue;
+            }
+
+            // Dialog lines may not follow the exit of a node
+            if !matches!(node.exit, NodeExit::FallThrough) {
+                return Err(Error::MalformedDocument);
+            }
+
+            node.lines.push(DialogLine::parse(escaped.unwrap_or(line)));
+        }
+
+        if let Some(node) = current.take() {
+            document.push_node(node)?;
+        }
+
+        document.validate_targets()?;
+
+        Ok(document)
+    }
+
+    /// Builds a document from nodes that were put together in code, e.g. by an importer, with
+    /// the same checks as `parse`.
+    pub(crate) fn from_nodes(nodes: Vec<DialogNode>) -> Result<Self, Error> {
+        let mut document = Self::default();
+
+        for node in nodes {
+            if !is_valid_label(&node.label) {
+                return Err(Error::MalformedDocument);
+            }
+
+            document.push_node(node)?;
+        }
+
+        document.validate_targets()?;
+
+        Ok(document)
+    }
+
+    pub fn nodes(&self) -> &[DialogNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, label: &str) -> Option<&DialogNode> {
+        self.labels.get(label).map(|&index| &self.nodes[index])
+    }
+
+    /// Every dialog line and choice of the document with its ID, in document order.
+    pub fn texts(&self) -> Vec<(TextId, &str)> {
+        let mut texts = Vec::new();
+
+        for node in &self.nodes {
+            for (index, line) in node.lines.iter().enumerate() {
+                let id = TextId::Line {
+                    node: node.label.clone(),
+                    index,
+                };
+                texts.push((id, line.text()));
+            }
+
+            if let NodeExit::Choices(choices) = &node.exit {
+                for (index, choice) in choices.iter().enumerate() {
+                    let id = TextId::Choice {
+                        node: node.label.clone(),
+                        index,
+                    };
+                    texts.push((id, choice.text()));
+                }
+            }
+        }
+
+        texts
+    }
+
+    pub fn text(&self, id: &TextId) -> Option<&str> {
+        match id {
+            TextId::Line { node, index } => {
+                self.node(node)?.lines.get(*index).map(DialogLine::text)
+            }
+            TextId::Choice { node, index } => match &self.node(node)?.exit {
+                NodeExit::Choices(choices) => choices.get(*index).map(DialogChoice::text),
+                _ => None,
+            },
+        }
+    }
+
+    /// Replaces the text of a dialog line or choice, e.g. with a translation.
+    pub fn set_text(&mut self, id: &TextId, text: String) -> Result<(), Error> {
+        let (TextId::Line { node, .. } | TextId::Choice { node, .. }) = id;
+
+        let Some(&node) = self.labels.get(node) else {
+            return Err(Error::UnknownTextId);
+        };
+
+        let node = &mut self.nodes[node];
+
+        let target = match (id, &mut node.exit) {
+            (TextId::Line { index, .. }, _) => {
+                node.lines.get_mut(*index).map(|line| &mut line.text)
+            }
+            (TextId::Choice { index, .. }, NodeExit::Choices(choices)) => {
+                choices.get_mut(*index).map(|choice| &mut choice.text)
+            }
+            (TextId::Choice { .. }, _) => None,
+        };
+
+        let Some(target) = target else {
+            return Err(Error::UnknownTextId);
+        };
+
+        *target = text;
+
+        Ok(())
+    }
+
+    pub(crate) fn node_at(&self, index: usize) -> Option<&DialogNode> {
+        self.nodes.get(index)
+    }
+
+    pub(crate) fn index_of(&self, label: &str) -> Option<usize> {
+        self.labels.get(label).copied()
+    }
+
+    fn push_node(&mut self, node: DialogNode) -> Result<(), Error> {
+        if self.labels.contains_key(&node.label) {
+            return Err(Error::DuplicateNodeLabel);
+        }
+
+        self.labels.insert(node.label.clone(), self.nodes.len());
+        self.nodes.push(node);
+
+        Ok(())
+    }
+
+    fn validate_targets(&self) -> Result<(), Error> {
+        let is_known = |target: &str| target == END_LABEL || self.labels.contains_key(target);
+
+        for node in &self.nodes {
+            let valid = match &node.exit {
+                NodeExit::FallThrough | NodeExit::End => true,
+                NodeExit::Jump(target) => is_known(target),
+                NodeExit::Choices(choices) => choices.iter().all(|choice| is_known(&choice.target)),
+            };
+
+            if !valid {
+                return Err(Error::UnknownNodeLabel);
+            }
+        }
+
+        // Nodes without lines that lead back to themselves would keep the runner from ever
+        // returning a line, choices or the end
+        for start in 0..self.nodes.len() {
+            let mut visited = vec![false; self.nodes.len()];
+            let mut index = start;
+
+            while self.nodes[index].lines.is_empty() {
+                if std::mem::replace(&mut visited[index], true) {
+                    return Err(Error::EmptyNodeCycle);
+                }
+
+                index = match &self.nodes[index].exit {
+                    NodeExit::FallThrough if index + 1 < self.nodes.len() => index + 1,
+                    NodeExit::Jump(target) => self.labels[target.as_str()],
+                    _ => break,
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Labels can't be empty, contain whitespace or dots, or be `END`.
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && !label.contains(|c: char| c.is_whitespace() || c == '.')
+        && label != END_LABEL
+}
+
+fn parse_target(target: &str) -> Result<String, Error> {
+    let target = target.trim();
+
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return Err(Error::MalformedDocument);
+    }
+
+    Ok(target.to_string())
+}
+
+/// Whether a line starting with the text would not be read as a dialog line.
+fn needs_escape(text: &str) -> bool {
+    let text = text.trim_start();
+    text.is_empty()
+        || SYNTAX_PREFIXES
+            .iter()
+            .any(|prefix| text.starts_with(prefix))
+}
+
+/// Writes the document as XYR source, which parses back into the same document.
+impl Display for XyrDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "=== {} ===", node.label)?;
+
+            for line in &node.lines {
+                match &line.speaker {
+                    Some(speaker) if needs_escape(speaker) => {
+                        writeln!(f, "{ESCAPE}{speaker}\t{}", line.text)?
+                    }
+                    Some(speaker) => writeln!(f, "{speaker}\t{}", line.text)?,
+                    // An empty speaker keeps the tabs in the text
+                    None if line.text.contains('\t') => writeln!(f, "{ESCAPE}\t{}", line.text)?,
+                    None if needs_escape(&line.text) => writeln!(f, "{ESCAPE}{}", line.text)?,
+                    None => writeln!(f, "{}", line.text)?,
+                }
+            }
+
+            match &node.exit {
+                NodeExit::FallThrough => {}
+                NodeExit::Jump(target) => writeln!(f, "-> {target}")?,
+                NodeExit::Choices(choices) => {
+                    for choice in choices {
+                        writeln!(f, "* {} -> {}", choice.text, choice.target)?;
+                    }
+                }
+                NodeExit::End => writeln!(f, "-> {END_LABEL}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for TextId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextId::Line { node, index } => write!(f, "{node}.{index}"),
+            TextId::Choice { node, index } => write!(f, "{node}.choice.{index}"),
+        }
+    }
+}
+
+impl FromStr for TextId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((prefix, index)) = s.rsplit_once('.') else {
+            return Err(Error::UnknownTextId);
+        };
+
+        let Ok(index) = index.parse() else {
+            return Err(Error::UnknownTextId);
+        };
+
+        let id = match prefix.strip_suffix(".choice") {
+            Some(node) => TextId::Choice {
+                node: node.to_string(),
+                index,
+            },
+            None => TextId::Line {
+                node: prefix.to_string(),
+                index,
+            },
+        };
+
+        Ok(id)
+    }
+}
+
+impl DialogNode {
+    fn new(label: String) -> Self {
+        Self::from_parts(label, Vec::new(), NodeExit::default())
+    }
+
+    pub(crate) fn from_parts(label: String, lines: Vec<DialogLine>, exit: NodeExit) -> Self {
+        Self { label, lines, exit }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn lines(&self) -> &[DialogLine] {
+        &self.lines
+    }
+
+    pub fn exit(&self) -> &NodeExit {
+        &self.exit
+    }
+}
+
+impl DialogLine {
+    pub(crate) fn new(speaker: Option<String>, text: String) -> Self {
+        Self { speaker, text }
+    }
+
+    /// Splits a line into its optional speaker template and the spoken text. The two are
+    /// separated by a tab character. An empty speaker is no speaker.
+    fn parse(line: &str) -> Self {
+        match line.split_once('\t') {
+            Some((speaker, text)) => Self {
+                speaker: Some(speaker.trim().to_string()).filter(|speaker| !speaker.is_empty()),
+                text: text.trim().to_string(),
+            },
+            None => Self {
+                speaker: None,
+                text: line.trim().to_string(),
+            },
+        }
+    }
+
+    pub fn speaker(&self) -> Option<&str> {
+        self.speaker.as_deref()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl DialogChoice {
+    pub(crate) fn new(text: String, target: String) -> Self {
+        Self { text, target }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    type Res = Result<(), crate::Error>;
+
+    pub(crate) const BRANCHING: &str = r#"
+=== start ===
+{"character_id":"hunk","type":"Name","data":null,"mods":[]}	Hey! Have you met {"character_id":"pidge","type":"Name","data":null,"mods":[]}?
+* Who? -> intro
+* Bye. -> END
+
+=== intro ===
+{"character_id":"hunk","type":"Name","data":null,"mods":[]}	{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":[]} our engineer.
+-> start
+"#;
+
+    #[test]
+    fn parse_document() -> Res {
+        let document = XyrDocument::parse(BRANCHING)?;
+
+        assert_eq!(document.nodes().len(), 2);
+
+        let start = document.node("start").unwrap();
+        assert_eq!(start.lines().len(), 1);
+        assert!(start.lines()[0].speaker().is_some());
+        assert!(start.lines()[0].text().starts_with("Hey!"));
+
+        let NodeExit::Choices(choices) = start.exit() else {
+            panic!("expected choices");
+        };
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices[0].text(), "Who?");
+        assert_eq!(choices[0].target(), "intro");
+        assert_eq!(choices[1].target(), END_LABEL);
+
+        let intro = document.node("intro").unwrap();
+        assert_eq!(intro.exit(), &NodeExit::Jump("start".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn flat_document() -> Res {
+        let document = XyrDocument::parse("First line\nSecond line")?;
+
+        assert_eq!(document.nodes().len(), 1);
+        assert_eq!(document.nodes()[0].label(), START_LABEL);
+        assert_eq!(document.nodes()[0].lines().len(), 2);
+        assert_eq!(document.nodes()[0].exit(), &NodeExit::FallThrough);
+
+        Ok(())
+    }
+
+    #[test]
+    fn text_ids() -> Res {
+        let mut document = XyrDocument::parse(BRANCHING)?;
+
+        let ids: Vec<String> = document
+            .texts()
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect();
+        assert_eq!(
+            ids,
+            ["start.0", "start.choice.0", "start.choice.1", "intro.0"]
+        );
+
+        for id in ids {
+            assert_eq!(id.parse::<TextId>()?.to_string(), id);
+        }
+
+        let who: TextId = "start.choice.0".parse()?;
+        document.set_text(&who, "Who's that?".to_string())?;
+        assert_eq!(document.text(&who), Some("Who's that?"));
+
+        assert!(matches!(
+            "start".parse::<TextId>(),
+            Err(Error::UnknownTextId)
+        ));
+        assert!(matches!(
+            document.set_text(&"intro.choice.0".parse()?, String::new()),
+            Err(Error::UnknownTextId)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_document() -> Res {
+        let document = XyrDocument::parse(BRANCHING)?;
+        let written = document.to_string();
+
+        assert!(written.starts_with("=== start ===\n"));
+        assert!(written.contains("* Bye. -> END\n\n=== intro ===\n"));
+
+        let reparsed = XyrDocument::parse(&written)?;
+        assert_eq!(reparsed.texts(), document.texts());
+        assert_eq!(
+            reparsed.node("intro").unwrap().exit(),
+            document.node("intro").unwrap().exit()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn escaped_lines() -> Res {
+        let document = XyrDocument::parse(
+            "\\* sighs * Fine.\n\\-> not a jump\n  \\=== not a node ===\n\\\tTabbed\ttext\n\\// Hi\n-> END",
+        )?;
+        let texts: Vec<&str> = document.texts().into_iter().map(|(_, text)| text).collect();
+
+        assert_eq!(
+            texts,
+            [
+                "* sighs * Fine.",
+                "-> not a jump",
+                "=== not a node ===",
+                "Tabbed\ttext",
+                "// Hi"
+            ]
+        );
+        assert!(document.nodes()[0]
+            .lines()
+            .iter()
+            .all(|line| line.speaker().is_none()));
+
+        let mut document = XyrDocument::parse("Hi\nHo\nHa\n* Bye -> END")?;
+        let id = |index| TextId::Line {
+            node: START_LABEL.to_string(),
+            index,
+        };
+        document.set_text(&id(0), "* item".to_string())?;
+        document.set_text(&id(1), "a\tb".to_string())?;
+        document.set_text(&id(2), "\\o/".to_string())?;
+
+        let reparsed = XyrDocument::parse(&document.to_string())?;
+        assert_eq!(reparsed.texts(), document.texts());
+        assert_eq!(reparsed.to_string(), document.to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn document_errors() {
+        assert!(matches!(
+            XyrDocument::parse("=== a ===\nHi\n=== a ===\nHo"),
+            Err(Error::DuplicateNodeLabel)
+        ));
+
+        assert!(matches!(
+            XyrDocument::parse("Hi\n-> nowhere"),
+            Err(Error::UnknownNodeLabel)
+        ));
+
+        assert!(matches!(
+            XyrDocument::parse("Hi\n-> END\nHo"),
+            Err(Error::MalformedDocument)
+        ));
+
+        assert!(matches!(
+            XyrDocument::parse("Hi\n* Choice without target"),
+            Err(Error::MalformedDocument)
+        ));
+
+        assert!(matches!(
+            XyrDocument::parse("=== a ===\n-> a"),
+            Err(Error::EmptyNodeCycle)
+        ));
+        assert!(matches!(
+            XyrDocument::parse("=== a ===\n=== b ===\n-> a"),
+            Err(Error::EmptyNodeCycle)
+        ));
+
+        // Jumping back is fine once a node has a line
+        assert!(XyrDocument::parse("=== a ===\nHi\n=== b ===\n-> a").is_ok());
+
+        // `a.choice.0` would name both the first line of `a.choice` and the first choice of `a`
+        assert!(matches!(
+            XyrDocument::parse("=== a ===\n* Hi -> a.choice\n=== a.choice ===\nHo"),
+            Err(Error::MalformedDocument)
+        ));
+    }
+}
@@ -0,0 +1,465 @@
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource};
+use fluent_syntax::ast::{Expression, InlineExpression, Pattern, PatternElement};
+
+use crate::{
+    character::{GrammaticalCharacter, Pronouns},
+    context::RenderContext,
+    dialog_parser::{DialogMacro, DialogMacroType, MacroHandler},
+    grammar::{English, Gender, Grammar, Inflection, PronounForm},
+    variables::Value,
+    verbs::ConjugatePerson,
+    Error,
+};
+
+/// The Fluent variables of a character, without a prefix: `$name`, `$pronouns`, `$subjective`
+/// and so on. See [`set_character_args`] for the full list.
+pub fn character_args(
+    grammar: &dyn Grammar,
+    character: &GrammaticalCharacter,
+) -> FluentArgs<'static> {
+    let mut args = FluentArgs::new();
+    set_character_args(&mut args, None, grammar, character);
+    args
+}
+
+/// Adds the grammar data of a character to Fluent arguments, so that messages can select on it.
+/// With a prefix like `hero`, the variables are named `$hero-name`, `$hero-pronouns` and so on.
+///
+/// Variable | Values
+/// --- | ---
+/// `name`, `title-name`, `descriptor` | The character as the grammar renders them
+/// `title` | The localized title, if the character has one
+/// `pronouns` | `he-him`, `she-her`, `it-its`, `they-them`, `name`, `xe-xyr` or `custom`
+/// `person` | The conjugate case: `first-singular` up to `third-plural`
+/// `number` | `singular` or `plural`
+/// `gender` | `masculine`, `feminine`, `neuter` or `neutral`
+/// `subjective`, `objective`, `possessive-determiner`, `possessive`, `reflexive` | The pronouns
+pub fn set_character_args(
+    args: &mut FluentArgs<'_>,
+    prefix: Option<&str>,
+    grammar: &dyn Grammar,
+    character: &GrammaticalCharacter,
+) {
+    let mut set = |key: &str, value: String| match prefix {
+        Some(prefix) => args.set(format!("{prefix}-{key}"), value),
+        None => args.set(key.to_string(), value),
+    };
+
+    set("name", character.name().to_string());
+    set("title-name", grammar.title_plus_name(character));
+    set("descriptor", grammar.person_descriptor(character));
+
+    if let Some(title) = character.title() {
+        set("title", grammar.title(title));
+    }
+
+    set("pronouns", pronoun_set(character.pronouns()).to_string());
+    set(
+        "person",
+        person(grammar.conjugate_person(character)).to_string(),
+    );
+    set(
+        "number",
+        if grammar.is_plural(character) {
+            "plural"
+        } else {
+            "singular"
+        }
+        .to_string(),
+    );
+    set("gender", gender(grammar.gender(character)).to_string());
+
+    let inflection = Inflection::default();
+
+    for (key, form) in [
+        ("subjective", PronounForm::Subjective),
+        ("objective", PronounForm::Objective),
+        ("possessive-determiner", PronounForm::PossessiveDeterminer),
+        ("possessive", PronounForm::Possessive),
+        ("reflexive", PronounForm::Reflexive),
+    ] {
+        set(key, grammar.pronoun(character, form, &inflection));
+    }
+}
+
+fn pronoun_set(pronouns: &Pronouns) -> &'static str {
+    match pronouns {
+        Pronouns::HeHim => "he-him",
+        Pronouns::SheHer => "she-her",
+        Pronouns::ItIts => "it-its",
+        Pronouns::TheyThem => "they-them",
+        Pronouns::Name => "name",
+        Pronouns::XeXyr => "xe-xyr",
+        Pronouns::Custom { .. } => "custom",
+    }
+}
+
+fn person(person: ConjugatePerson) -> &'static str {
+    match person {
+        ConjugatePerson::FirstSingular => "first-singular",
+        ConjugatePerson::SecondSingular => "second-singular",
+        ConjugatePerson::ThirdSingular => "third-singular",
+        ConjugatePerson::FirstPlural => "first-plural",
+        ConjugatePerson::SecondPlural => "second-plural",
+        ConjugatePerson::ThirdPlural => "third-plural",
+    }
+}
+
+fn gender(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Masculine => "masculine",
+        Gender::Feminine => "feminine",
+        Gender::Neuter => "neuter",
+        Gender::Neutral => "neutral",
+    }
+}
+
+/// A custom macro type that renders a Fluent message with the variables of the macro's
+/// character, so that Fluent messages can be embedded in XYR:
+///
+/// `{"character_id":"pidge","type":"Fluent","data":"greeting","mods":[]}`
+///
+/// The data is the message ID, optionally followed by an attribute: `greeting.formal`. Register
+/// it with [`DialogMacroCompiler::with_macro`](crate::DialogMacroCompiler::with_macro). The
+/// pronouns and titles passed to Fluent are rendered by the grammar of the compiler, and other
+/// variables of the message are looked up in the render context.
+pub struct FluentMacro {
+    bundle: FluentBundle<FluentResource>,
+}
+
+impl FluentMacro {
+    /// Turns off the Unicode isolation of placeables in the bundle, which would wrap every name and
+    /// pronoun in invisible directional marks in the middle of the dialog.
+    pub fn new(mut bundle: FluentBundle<FluentResource>) -> Self {
+        bundle.set_use_isolating(false);
+        Self { bundle }
+    }
+
+    pub fn bundle(&self) -> &FluentBundle<FluentResource> {
+        &self.bundle
+    }
+
+    /// Converts a message into an XYR template, the other way around. Character variables
+    /// become macros, for the given character if they have no prefix: `{ $hero-subjective }`
+    /// becomes a `SubjectivePronoun` macro for `hero`. Other variables become `Variable` macros.
+    ///
+    /// Selectors, functions and references to other messages have no XYR equivalent and fail with
+    /// [`Error::UnsupportedFluentSyntax`].
+    pub fn to_xyr(&self, id: &str, character_id: Option<&str>) -> Result<String, Error> {
+        let mut xyr = String::new();
+
+        for element in &self.pattern(id)?.elements {
+            match element {
+                PatternElement::TextElement { value } => xyr.push_str(&escape(value)),
+                PatternElement::Placeable { expression } => {
+                    xyr.push_str(&expression_xyr(expression, character_id)?)
+                }
+            }
+        }
+
+        Ok(xyr)
+    }
+
+    /// The pattern of a message ID, optionally followed by an attribute.
+    fn pattern(&self, data: &str) -> Result<&Pattern<&str>, Error> {
+        let (id, attribute) = match data.split_once('.') {
+            Some((id, attribute)) => (id, Some(attribute)),
+            None => (data, None),
+        };
+
+        let message = self
+            .bundle
+            .get_message(id)
+            .ok_or(Error::UnknownFluentMessage)?;
+
+        match attribute {
+            Some(attribute) => message.get_attribute(attribute).map(|a| a.value()),
+            None => message.value(),
+        }
+        .ok_or(Error::UnknownFluentMessage)
+    }
+}
+
+impl MacroHandler for FluentMacro {
+    /// Renders the message with English pronouns and titles. Compilers call `render_in` with
+    /// their own grammar instead.
+    fn render(
+        &self,
+        character: Option<&GrammaticalCharacter>,
+        data: Option<&str>,
+        ctx: &RenderContext,
+    ) -> Result<String, Error> {
+        self.render_in(&English, character, data, ctx)
+    }
+
+    fn render_in(
+        &self,
+        grammar: &dyn Grammar,
+        character: Option<&GrammaticalCharacter>,
+        data: Option<&str>,
+        ctx: &RenderContext,
+    ) -> Result<String, Error> {
+        let Some(data) = data else {
+            return Err(Error::MissingMacroData);
+        };
+
+        let pattern = self.pattern(data)?;
+
+        let mut args = match character {
+            Some(character) => character_args(grammar, character),
+            None => FluentArgs::new(),
+        };
+
+        if let Some(variables) = ctx.variables() {
+            let mut names = Vec::new();
+            pattern_variables(pattern, &mut names);
+
+            for name in names {
+                if args.get(name).is_some() {
+                    continue;
+                }
+
+                match variables.get(name) {
+                    Some(Value::Text(text)) => args.set(name, text),
+                    Some(value) => args.set(name, value.as_f64().unwrap_or_default()),
+                    None => {}
+                }
+            }
+        }
+
+        let mut errors = Vec::new();
+        let output = self
+            .bundle
+            .format_pattern(pattern, Some(&args), &mut errors);
+
+        if !errors.is_empty() {
+            return Err(Error::FluentFormat);
+        }
+
+        Ok(output.into_owned())
+    }
+}
+
+/// Collects the names of the variables a pattern refers to, including those in selectors.
+fn pattern_variables<'p>(pattern: &'p Pattern<&str>, names: &mut Vec<&'p str>) {
+    for element in &pattern.elements {
+        if let PatternElement::Placeable { expression } = element {
+            expression_variables(expression, names);
+        }
+    }
+}
+
+fn expression_variables<'p>(expression: &'p Expression<&str>, names: &mut Vec<&'p str>) {
+    match expression {
+        Expression::Select { selector, variants } => {
+            inline_variables(selector, names);
+
+            for variant in variants {
+                pattern_variables(&variant.value, names);
+            }
+        }
+        Expression::Inline(inline) => inline_variables(inline, names),
+    }
+}
+
+fn inline_variables<'p>(inline: &'p InlineExpression<&str>, names: &mut Vec<&'p str>) {
+    match inline {
+        InlineExpression::VariableReference { id } => names.push(id.name),
+        InlineExpression::FunctionReference { arguments, .. } => {
+            for argument in &arguments.positional {
+                inline_variables(argument, names);
+            }
+
+            for argument in &arguments.named {
+                inline_variables(&argument.value, names);
+            }
+        }
+        InlineExpression::Placeable { expression } => expression_variables(expression, names),
+        _ => {}
+    }
+}
+
+/// The XYR text of a placeable.
+fn expression_xyr(
+    expression: &Expression<&str>,
+    character_id: Option<&str>,
+) -> Result<String, Error> {
+    match expression {
+        Expression::Inline(InlineExpression::StringLiteral { value })
+        | Expression::Inline(InlineExpression::NumberLiteral { value }) => Ok(escape(value)),
+        Expression::Inline(InlineExpression::VariableReference { id }) => {
+            Ok(variable_xyr(id.name, character_id))
+        }
+        Expression::Inline(InlineExpression::Placeable { expression }) => {
+            expression_xyr(expression, character_id)
+        }
+        _ => Err(Error::UnsupportedFluentSyntax),
+    }
+}
+
+/// The macro of a variable, the reverse of [`set_character_args`].
+fn variable_xyr(name: &str, character_id: Option<&str>) -> String {
+    // Longer keys first, so `hero-title-name` isn't read as the `name` of `hero-title`
+    let keys = [
+        (
+            "possessive-determiner",
+            DialogMacroType::PossessiveDeterminer,
+        ),
+        ("title-name", DialogMacroType::TitlePlusName),
+        ("descriptor", DialogMacroType::PersonDescriptor),
+        ("subjective", DialogMacroType::SubjectivePronoun),
+        ("objective", DialogMacroType::ObjectivePronoun),
+        ("possessive", DialogMacroType::PossessivePronoun),
+        ("reflexive", DialogMacroType::ReflexivePronoun),
+        ("name", DialogMacroType::Name),
+    ];
+
+    let key = keys.into_iter().find(|(key, _)| {
+        name.strip_suffix(key)
+            .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('-'))
+    });
+
+    let character = key.and_then(|(key, _type)| {
+        let character = match name[..name.len() - key.len()].strip_suffix('-') {
+            Some(prefix) => prefix,
+            None => character_id?,
+        };
+
+        Some((character, _type))
+    });
+
+    let macr = match character {
+        Some((character, _type)) => DialogMacro::new(Some(character), _type, None, Vec::new()),
+        None => DialogMacro::new(None, DialogMacroType::Variable, Some(name), Vec::new()),
+    };
+
+    serde_json::to_string(&macr).unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::{character, verbs, DialogMacroCompiler, German};
+
+    type Res = Result<(), crate::Error>;
+
+    const MESSAGES: &str = r#"
+arrival = { $pronouns ->
+    [he-him] He has
+    [she-her] She has
+   *[other] { $subjective } { $number ->
+        [plural] have
+       *[singular] has
+    }
+} arrived.
+    .formal = { $title-name } has arrived.
+greeting = Hallo, { $title-name }!
+score = { $subjective } scored { $points ->
+    [one] one point
+   *[other] { $points } points
+}.
+intro = Meet {"{"}{ $hero-title-name }{"}"}, { $hero-subjective } will help { $name }.
+"#;
+
+    fn gen_bundle() -> FluentBundle<FluentResource> {
+        let mut bundle = FluentBundle::default();
+        bundle
+            .add_resource(FluentResource::try_new(MESSAGES.to_string()).unwrap())
+            .unwrap();
+        bundle
+    }
+
+    #[test]
+    fn args_test() {
+        let [pidge, _, _, hunk] = character::tests::get_characters();
+
+        let args = character_args(&English, &pidge);
+        assert_eq!(args.get("pronouns"), Some(&"they-them".into()));
+        assert_eq!(args.get("person"), Some(&"third-plural".into()));
+        assert_eq!(args.get("subjective"), Some(&"they".into()));
+
+        let mut args = FluentArgs::new();
+        set_character_args(&mut args, Some("hero"), &German::new(), &hunk);
+        assert_eq!(args.get("hero-name"), Some(&"Hunk".into()));
+        assert_eq!(args.get("hero-gender"), Some(&"masculine".into()));
+        assert_eq!(args.get("hero-objective"), Some(&"ihn".into()));
+    }
+
+    #[test]
+    fn fluent_macro_test() -> Res {
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict())
+                .with_macro("Fluent", FluentMacro::new(gen_bundle()));
+
+        let source = r#"{"character_id":"hunk","type":"Fluent","data":"arrival","mods":[]} {"character_id":"pidge","type":"Fluent","data":"arrival","mods":["Capitalized"]}"#;
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "He has arrived. They have arrived."
+        );
+
+        let source = r#"{"character_id":"hunk","type":"Fluent","data":"arrival.formal","mods":[]}"#;
+        assert_eq!(compiler.parse_and_compile(source)?, "Mr. Hunk has arrived.");
+
+        let source = r#"{"character_id":"hunk","type":"Fluent","data":"farewell","mods":[]}"#;
+        assert!(matches!(
+            compiler.parse_and_compile(source),
+            Err(Error::UnknownFluentMessage)
+        ));
+
+        // Other variables come from the render context
+        let variables = HashMap::from([("points".to_string(), Value::from(3))]);
+        let ctx = RenderContext::new().with_variables(&variables);
+        let source = r#"{"character_id":"hunk","type":"Fluent","data":"score","mods":[]}"#;
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "he scored 3 points."
+        );
+
+        // Pronouns and titles are rendered in the language of the compiler
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict())
+                .with_grammar(German::new())
+                .with_macro("Fluent", FluentMacro::new(gen_bundle()));
+
+        let source = r#"{"character_id":"hunk","type":"Fluent","data":"greeting","mods":[]}"#;
+        assert_eq!(compiler.parse_and_compile(source)?, "Hallo, Herr Hunk!");
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_xyr_test() -> Res {
+        let fluent = FluentMacro::new(gen_bundle());
+
+        assert_eq!(
+            fluent.to_xyr("intro", Some("pidge"))?,
+            r#"Meet {{{"character_id":"hero","type":"TitlePlusName","data":null,"mods":[]}}}, {"character_id":"hero","type":"SubjectivePronoun","data":null,"mods":[]} will help {"character_id":"pidge","type":"Name","data":null,"mods":[]}."#
+        );
+        assert_eq!(
+            fluent.to_xyr("greeting", None)?,
+            r#"Hallo, {"character_id":null,"type":"Variable","data":"title-name","mods":[]}!"#
+        );
+        assert!(matches!(
+            fluent.to_xyr("arrival", Some("pidge")),
+            Err(Error::UnsupportedFluentSyntax)
+        ));
+
+        // The template renders like the message
+        let mut cast = character::tests::gen_cast();
+        cast.insert("hero".to_string(), cast.get("hunk").unwrap().clone());
+        let compiler = DialogMacroCompiler::new(cast, verbs::tests::gen_dict());
+        let template = fluent.to_xyr("intro", Some("pidge"))?;
+        assert_eq!(
+            compiler.parse_and_compile(&template)?,
+            "Meet {Mr. Hunk}, he will help Pidge."
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! The versioned JSON format of cast and dictionary files.
+//!
+//! Files carry the version of their layout in a `version` field. Files without one are version 0,
+//! the unversioned layout of earlier releases that wrapped characters and verbs in a `"map"` and
+//! named the type of a macro `_type`.
+//! Keys that a version renamed stay readable under their old names as serde aliases, so files of
+//! any version deserialize in one step, also as part of other data like save files, and
+//! dictionaries keep borrowing from their source. The migrate functions below rewrite older
+//! files in the current layout.
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::{
+    character::CharacterCast, dialog_parser::DialogMacro, nouns::NounDictionary, verbs::Dictionary,
+    Error,
+};
+
+/// The version of the layout that `to_json` writes.
+pub const FORMAT_VERSION: u64 = 1;
+
+const VERSION_KEY: &str = "version";
+
+/// The `version` field of a file, which rejects versions newer than `FORMAT_VERSION`.
+#[derive(Debug, Default)]
+pub(crate) struct Version;
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if u64::deserialize(deserializer)? <= FORMAT_VERSION {
+            Ok(Version)
+        } else {
+            Err(de::Error::custom(Error::UnsupportedVersion))
+        }
+    }
+}
+
+/// Rewrites a cast file of any version in the current layout.
+pub fn migrate_cast(src: &str) -> Result<String, Error> {
+    CharacterCast::from_json(src)?.to_json()
+}
+
+/// Rewrites a verb dictionary file of any version in the current layout.
+pub fn migrate_dictionary(src: &str) -> Result<String, Error> {
+    Dictionary::from_json(src)?.to_json()
+}
+
+/// Rewrites a noun dictionary file of any version in the current layout.
+pub fn migrate_nouns(src: &str) -> Result<String, Error> {
+    NounDictionary::from_json(src)?.to_json()
+}
+
+/// Rewrites a macro of any version in the current layout, e.g. `_type` as `type`.
+///
+/// Macros are embedded in dialog and don't carry a version. Their older keys stay readable, so
+/// this is only needed to update the dialog files themselves.
+pub fn migrate_macro(src: &str) -> Result<String, Error> {
+    Ok(serde_json::to_string(
+        &serde_json::from_str::<DialogMacro>(src)?,
+    )?)
+}
+
+/// Writes the data in the current layout, with its `version` field.
+pub(crate) fn to_json<T: Serialize>(data: &T) -> Result<String, Error> {
+    let mut value = serde_json::to_value(data)?;
+
+    if let Value::Object(object) = &mut value {
+        object.insert(VERSION_KEY.to_string(), FORMAT_VERSION.into());
+    }
+
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character::{CharacterCast, Pronouns},
+        nouns::NounDictionary,
+        verbs::{ConjugatePerson, Dictionary},
+        English,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    const LEGACY_CAST: &str = r#"{
+        "map": {
+            "pidge": {
+                "name": "Pidge",
+                "pronouns": "TheyThem",
+                "title": "NoTitle",
+                "person_descriptor": "Person"
+            }
+        },
+        "groups": {"team": ["pidge"]}
+    }"#;
+
+    const LEGACY_DICTIONARY: &str = r#"{
+        "map": {
+            "to be": {
+                "debug_ident": "to be",
+                "infinitive": "be",
+                "singular1": "am",
+                "singular2": "are",
+                "singular3": "is",
+                "plural1": "are",
+                "plural2": "are",
+                "plural3": "are"
+            }
+        }
+    }"#;
+
+    #[test]
+    fn cast_test() -> Res {
+        let cast = CharacterCast::from_json(LEGACY_CAST)?;
+        assert_eq!(cast.get("pidge").unwrap().pronouns(), &Pronouns::TheyThem);
+
+        let json = cast.to_json()?;
+        let value: Value = serde_json::from_str(&json)?;
+        assert_eq!(value[VERSION_KEY], 1);
+        assert_eq!(value["characters"]["pidge"]["name"], "Pidge");
+        assert_eq!(value["groups"]["team"][0], "pidge");
+        assert!(value.get("map").is_none());
+
+        // Current files load unchanged
+        assert_eq!(CharacterCast::from_json(&json)?.to_json()?, json);
+        assert_eq!(migrate_cast(&json)?, json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn embedded_cast_test() -> Res {
+        #[derive(Deserialize)]
+        struct Save {
+            cast: CharacterCast,
+        }
+
+        let save: Save = serde_json::from_str(&format!(r#"{{"cast": {LEGACY_CAST}}}"#))?;
+        assert_eq!(save.cast.get("pidge").unwrap().name(), "Pidge");
+        assert_eq!(save.cast.group("team"), Some(&["pidge".to_string()][..]));
+
+        assert!(
+            serde_json::from_str::<Save>(r#"{"cast": {"version": 2, "characters": {}}}"#).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn dictionary_test() -> Res {
+        let dictionary = Dictionary::from_json(LEGACY_DICTIONARY)?;
+        assert_eq!(
+            dictionary.conjugate("to be", ConjugatePerson::ThirdSingular)?,
+            "is"
+        );
+
+        let json = migrate_dictionary(LEGACY_DICTIONARY)?;
+        let value: Value = serde_json::from_str(&json)?;
+        assert_eq!(value[VERSION_KEY], 1);
+        assert_eq!(value["verbs"]["to be"]["singular3"], "is");
+        assert_eq!(Dictionary::from_json(&json)?.to_json()?, json);
+
+        let legacy_nouns = r#"{"map":{"mouse":{"singular":"mouse","plural":"mice"}}}"#;
+        let nouns = NounDictionary::from_json(legacy_nouns)?;
+        assert_eq!(nouns.plural("mouse", &English), "mice");
+
+        let json = migrate_nouns(legacy_nouns)?;
+        assert_eq!(NounDictionary::from_json(&json)?.to_json()?, json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn macro_test() -> Res {
+        let current = r#"{"character_id":"pidge","type":"Name","data":null,"mods":[]}"#;
+
+        assert_eq!(
+            migrate_macro(r#"{"character_id":"pidge","_type":"Name","data":null,"mods":[]}"#)?,
+            current
+        );
+        assert_eq!(migrate_macro(current)?, current);
+
+        Ok(())
+    }
+
+    #[test]
+    fn version_errors() {
+        let error = CharacterCast::from_json(r#"{"version":2,"characters":{}}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unsupported format version at line 1 column 12"
+        );
+        assert!(matches!(
+            Dictionary::from_json(r#"{"version":2,"verbs":{}}"#),
+            Err(Error::Serde(_))
+        ));
+        assert!(matches!(
+            migrate_dictionary(r#"{"version":"1","verbs":{}}"#),
+            Err(Error::Serde(_))
+        ));
+        assert!(matches!(
+            CharacterCast::from_json("[]"),
+            Err(Error::Serde(_))
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+
+use crate::{
+    dialog_parser::{delimit_macro, DialogMacro},
+    document::{TextId, XyrDocument},
+    Error,
+};
+
+/// A translatable text with its macros replaced by numbered placeholders: `Do you know {0}?`.
+pub(crate) struct Message {
+    pub(crate) id: TextId,
+    pub(crate) msgid: String,
+    pub(crate) macros: Vec<String>,
+}
+
+impl Message {
+    pub(crate) fn new(id: TextId, template: &str) -> Result<Self, Error> {
+        let mut msgid = String::new();
+        let mut macros = Vec::new();
+        let mut rest = template;
+
+        while let Some(index) = rest.find('{') {
+            msgid.push_str(&rest[..index]);
+            rest = &rest[index..];
+
+            // Escaped braces stay escaped, so that they can't be mistaken for placeholders
+            if rest.starts_with("{{") {
+                msgid.push_str("{{");
+                rest = &rest[2..];
+                continue;
+            }
+
+            let macro_str = delimit_macro(rest);
+            serde_json::from_str::<DialogMacro>(macro_str)?;
+
+            msgid.push_str(&format!("{{{}}}", macros.len()));
+            macros.push(macro_str.to_string());
+            rest = &rest[macro_str.len()..];
+        }
+
+        msgid.push_str(rest);
+
+        Ok(Self { id, msgid, macros })
+    }
+
+    /// Puts the macros back into a translation. Every placeholder has to be used at least once.
+    pub(crate) fn restore(&self, translation: &str) -> Result<String, Error> {
+        let mut output = String::new();
+        let mut used = vec![false; self.macros.len()];
+        let mut rest = translation;
+
+        while let Some(index) = rest.find('{') {
+            output.push_str(&rest[..index]);
+            rest = &rest[index..];
+
+            if rest.starts_with("{{") {
+                output.push_str("{{");
+                rest = &rest[2..];
+                continue;
+            }
+
+            let placeholder = rest
+                .find('}')
+                .and_then(|end| Some((end, rest[1..end].parse::<usize>().ok()?)));
+
+            let Some((end, index)) = placeholder else {
+                return Err(Error::UnknownPlaceholder);
+            };
+
+            let Some(macro_str) = self.macros.get(index) else {
+                return Err(Error::UnknownPlaceholder);
+            };
+
+            output.push_str(macro_str);
+            used[index] = true;
+            rest = &rest[end + 1..];
+        }
+
+        output.push_str(rest);
+
+        if used.contains(&false) {
+            return Err(Error::MissingPlaceholder);
+        }
+
+        Ok(output)
+    }
+}
+
+fn messages(document: &XyrDocument) -> Result<Vec<Message>, Error> {
+    document
+        .texts()
+        .into_iter()
+        .map(|(id, text)| Message::new(id, text))
+        .collect()
+}
+
+/// Writes a PO template with every dialog line and choice of the document.
+///
+/// The ID of each text is its message context. Macros are replaced by numbered placeholders like
+/// `{0}`, and a comment above each message shows the macro behind every placeholder.
+/// Translators may reorder and repeat placeholders, but have to keep all of them.
+pub fn extract_pot(document: &XyrDocument) -> Result<String, Error> {
+    let mut pot =
+        String::from("msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+    for message in messages(document)? {
+        pot.push('\n');
+
+        for (index, macro_str) in message.macros.iter().enumerate() {
+            pot.push_str(&format!("#. {{{index}}}: {macro_str}\n"));
+        }
+
+        pot.push_str(&format!("msgctxt {}\n", quote(&message.id.to_string())));
+        pot.push_str(&format!("msgid {}\n", quote(&message.msgid)));
+        pot.push_str("msgstr \"\"\n");
+    }
+
+    Ok(pot)
+}
+
+/// The translated texts of a document in one locale, read from a PO file.
+#[derive(Debug, Clone, Default)]
+pub struct Translations {
+    locale: Option<String>,
+    texts: HashMap<TextId, String>,
+}
+
+impl Translations {
+    /// Reads the translations of a document from a PO file written for it by [`extract_pot`].
+    ///
+    /// Untranslated and fuzzy messages are skipped, and so are messages whose source text
+    /// changed since the template was extracted. Fails if a translation drops a placeholder or
+    /// uses one that doesn't exist.
+    pub fn from_po(src: &str, document: &XyrDocument) -> Result<Self, Error> {
+        let messages: HashMap<String, Message> = messages(document)?
+            .into_iter()
+            .map(|message| (message.id.to_string(), message))
+            .collect();
+
+        let mut translations = Self::default();
+
+        for entry in parse_po(src)? {
+            if entry.msgid.is_empty() {
+                translations.locale = header_field(&entry.msgstr, "Language");
+                continue;
+            }
+
+            if entry.fuzzy || entry.msgstr.is_empty() {
+                continue;
+            }
+
+            let Some(message) = entry.context.and_then(|context| messages.get(&context)) else {
+                continue;
+            };
+
+            if message.msgid != entry.msgid {
+                continue;
+            }
+
+            let text = message.restore(&entry.msgstr)?;
+            translations.texts.insert(message.id.clone(), text);
+        }
+
+        Ok(translations)
+    }
+
+    /// The language of the PO file, from its `Language` header.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    /// The translated template of a text, with its macros restored.
+    pub fn get(&self, id: &TextId) -> Option<&str> {
+        self.texts.get(id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    /// A copy of the document with every translated text replaced. It is rendered like any
+    /// other document, with a compiler for the grammar of the locale.
+    pub fn apply(&self, document: &XyrDocument) -> Result<XyrDocument, Error> {
+        let mut translated = document.clone();
+
+        for (id, text) in &self.texts {
+            translated.set_text(id, text.clone())?;
+        }
+
+        Ok(translated)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PoEntry {
+    context: Option<String>,
+    msgid: String,
+    msgstr: String,
+    fuzzy: bool,
+}
+
+#[derive(Clone, Copy)]
+enum PoField {
+    Context,
+    Id,
+    Str,
+}
+
+/// Reads the entries of a PO file. Plural forms are not supported.
+fn parse_po(src: &str) -> Result<Vec<PoEntry>, Error> {
+    let mut entries = Vec::new();
+    let mut entry = PoEntry::default();
+    let mut field: Option<PoField> = None;
+    let mut fuzzy = false;
+
+    for line in src.lines() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            continue;
+        }
+
+        // Other comments, including obsolete entries
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = match line.split_once(char::is_whitespace) {
+            Some((keyword, value)) if !line.starts_with('"') => (Some(keyword), value.trim()),
+            _ => (None, line),
+        };
+
+        let value = unquote(value)?;
+
+        let next = match keyword {
+            None => match field {
+                Some(field) => field,
+                None => return Err(Error::MalformedPo),
+            },
+            Some("msgctxt") => PoField::Context,
+            Some("msgid") => PoField::Id,
+            Some("msgstr") => PoField::Str,
+            Some(_) => return Err(Error::MalformedPo),
+        };
+
+        // A context or ID after a message string, or at the start of the file, starts an entry
+        let starts_entry = keyword.is_some()
+            && !matches!(next, PoField::Str)
+            && matches!(field, None | Some(PoField::Str));
+
+        if starts_entry {
+            if field.is_some() {
+                entries.push(std::mem::take(&mut entry));
+            }
+
+            entry.fuzzy = std::mem::take(&mut fuzzy);
+        }
+
+        match next {
+            PoField::Context => entry
+                .context
+                .get_or_insert_with(String::new)
+                .push_str(&value),
+            PoField::Id => entry.msgid.push_str(&value),
+            PoField::Str => entry.msgstr.push_str(&value),
+        }
+
+        field = Some(next);
+    }
+
+    if field.is_some() {
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn header_field(header: &str, name: &str) -> Option<String> {
+    header.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == name && !value.trim().is_empty()).then(|| value.trim().to_string())
+    })
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+fn unquote(value: &str) -> Result<String, Error> {
+    let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return Err(Error::MalformedPo);
+    };
+
+    let mut unquoted = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unquoted.push('\n'),
+            Some('t') => unquoted.push('\t'),
+            Some(c @ ('"' | '\\')) => unquoted.push(c),
+            _ => return Err(Error::MalformedPo),
+        }
+    }
+
+    Ok(unquoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, document, DialogEvent, DialogMacroCompiler, DialogRunner, Dictionary, German,
+        RenderedLine, Verb,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    const GERMAN_PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: de\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#. {0}: {"character_id":"pidge","type":"Name","data":null,"mods":[]}
+msgctxt "start.0"
+msgid "Hey! Have you met {0}?"
+msgstr "Hey! Kennst du schon {0}?"
+
+#, fuzzy
+#, no-c-format
+msgctxt "start.choice.0"
+msgid "Who?"
+msgstr "Wer bitte?"
+
+msgctxt "start.choice.1"
+msgid "Bye."
+msgstr "Tschüss."
+
+msgctxt "intro.0"
+msgid "{0} {1} our engineer."
+msgstr ""
+"{0} {1} unsere "
+"Ingenieurin."
+"#;
+
+    #[test]
+    fn extract_test() -> Res {
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+        let pot = extract_pot(&document)?;
+
+        assert!(pot.contains(
+            "#. {0}: {\"character_id\":\"pidge\",\"type\":\"Name\",\"data\":null,\"mods\":[]}\nmsgctxt \"start.0\"\nmsgid \"Hey! Have you met {0}?\"\nmsgstr \"\"\n"
+        ));
+        assert!(pot.contains("msgctxt \"start.choice.1\"\nmsgid \"Bye.\"\n"));
+        assert!(pot.contains("msgctxt \"intro.0\"\nmsgid \"{0} {1} our engineer.\"\n"));
+
+        // The template itself is a valid PO file without translations
+        let translations = Translations::from_po(&pot, &document)?;
+        assert!(translations.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn translate_test() -> Res {
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+        let translations = Translations::from_po(GERMAN_PO, &document)?;
+
+        assert_eq!(translations.locale(), Some("de"));
+        assert_eq!(translations.len(), 3);
+        assert_eq!(translations.get(&"start.choice.0".parse()?), None);
+
+        let to_be = Verb::new(
+            "to be",
+            Some("sein"),
+            Some("bin"),
+            Some("bist"),
+            Some("ist"),
+            Some("sind"),
+            Some("seid"),
+            Some("sind"),
+        );
+
+        let mut dict = Dictionary::default();
+        dict.insert("to be".to_string(), to_be);
+
+        let compiler = DialogMacroCompiler::new(character::tests::gen_cast(), dict)
+            .with_grammar(German::new());
+        let translated = translations.apply(&document)?;
+        let mut runner = DialogRunner::new(&compiler, &translated);
+
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Hunk".to_string()),
+                text: "Hey! Kennst du schon Pidge?".to_string(),
+            })
+        );
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Choices(vec!["Who?".to_string(), "Tschüss.".to_string()])
+        );
+
+        runner.choose(0)?;
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Hunk".to_string()),
+                text: "Dey ist unsere Ingenieurin.".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn placeholder_errors() -> Res {
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+
+        let po = "msgctxt \"intro.0\"\nmsgid \"{0} {1} our engineer.\"\nmsgstr \"{0} unsere Ingenieurin.\"";
+        assert!(matches!(
+            Translations::from_po(po, &document),
+            Err(Error::MissingPlaceholder)
+        ));
+
+        let po = "msgctxt \"intro.0\"\nmsgid \"{0} {1} our engineer.\"\nmsgstr \"{0} {1} {2}.\"";
+        assert!(matches!(
+            Translations::from_po(po, &document),
+            Err(Error::UnknownPlaceholder)
+        ));
+
+        let po = "msgctxt \"intro.0\"\nmsgid \"{0} {1} our engineer.\"\nmsgstr \"{1} {0} {{ok}}.\"";
+        let translations = Translations::from_po(po, &document)?;
+        assert!(translations
+            .get(&"intro.0".parse()?)
+            .unwrap()
+            .ends_with("{{ok}}."));
+
+        assert!(matches!(
+            Translations::from_po("msgid_plural \"x\"", &document),
+            Err(Error::MalformedPo)
+        ));
+
+        Ok(())
+    }
+}
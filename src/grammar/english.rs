@@ -0,0 +1,378 @@
+use crate::{
+    character::{GrammaticalCharacter, Pronouns, Title},
+    verbs::ConjugatePerson,
+};
+
+use super::{Grammar, Inflection, PronounForm};
+
+/// English, the default grammar.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct English;
+
+impl Grammar for English {
+    fn locale(&self) -> &str {
+        "en"
+    }
+
+    /// English pronouns don't decline beyond their form, so the inflection is ignored.
+    fn pronoun(
+        &self,
+        character: &GrammaticalCharacter,
+        form: PronounForm,
+        _inflection: &Inflection,
+    ) -> String {
+        use PronounForm::*;
+
+        if let Some(person) = character.perspective() {
+            return perspective_pronoun(person, form).to_string();
+        }
+
+        match (character.pronouns(), form) {
+            (Pronouns::HeHim, Subjective) => "he".to_string(),
+            (Pronouns::HeHim, Objective) => "him".to_string(),
+            (Pronouns::HeHim, PossessiveDeterminer | Possessive) => "his".to_string(),
+            (Pronouns::HeHim, Reflexive) => "himself".to_string(),
+
+            (Pronouns::SheHer, Subjective) => "she".to_string(),
+            (Pronouns::SheHer, Objective | PossessiveDeterminer) => "her".to_string(),
+            (Pronouns::SheHer, Possessive) => "hers".to_string(),
+            (Pronouns::SheHer, Reflexive) => "herself".to_string(),
+
+            (Pronouns::ItIts, Subjective | Objective) => "it".to_string(),
+            (Pronouns::ItIts, PossessiveDeterminer | Possessive) => "its".to_string(),
+            (Pronouns::ItIts, Reflexive) => "itself".to_string(),
+
+            (Pronouns::TheyThem, Subjective) => "they".to_string(),
+            (Pronouns::TheyThem, Objective) => "them".to_string(),
+            (Pronouns::TheyThem, PossessiveDeterminer) => "their".to_string(),
+            (Pronouns::TheyThem, Possessive) => "theirs".to_string(),
+            (Pronouns::TheyThem, Reflexive) => "themself".to_string(),
+
+            (Pronouns::Name, Subjective | Objective) => character.name().to_string(),
+            (Pronouns::Name, PossessiveDeterminer | Possessive) => possessive(character.name()),
+            // FIXME: find a good solution for this case
+            (Pronouns::Name, Reflexive) => format!("{} self", possessive(character.name())),
+
+            (Pronouns::XeXyr, Subjective) => "xe".to_string(),
+            (Pronouns::XeXyr, Objective) => "xem".to_string(),
+            (Pronouns::XeXyr, PossessiveDeterminer) => "xyr".to_string(),
+            (Pronouns::XeXyr, Possessive) => "xyrs".to_string(),
+            (Pronouns::XeXyr, Reflexive) => "xyrself".to_string(),
+
+            (Pronouns::Custom { subjective, .. }, Subjective) => subjective.to_string(),
+            (Pronouns::Custom { objective, .. }, Objective) => objective.to_string(),
+            (
+                Pronouns::Custom {
+                    possessive_determiner,
+                    ..
+                },
+                PossessiveDeterminer,
+            ) => possessive_determiner.to_string(),
+            (Pronouns::Custom { possessive, .. }, Possessive) => possessive.to_string(),
+            (Pronouns::Custom { reflexive, .. }, Reflexive) => reflexive.to_string(),
+        }
+    }
+
+    fn conjugate_person(&self, character: &GrammaticalCharacter) -> ConjugatePerson {
+        if let Some(person) = character.perspective() {
+            return person;
+        }
+
+        use ConjugatePerson::*;
+        use Pronouns::*;
+        match character.pronouns() {
+            HeHim | SheHer | ItIts | XeXyr | Name => ThirdSingular,
+            TheyThem => ThirdPlural,
+            Pronouns::Custom { conjugate_case, .. } => *conjugate_case,
+        }
+    }
+
+    fn title(&self, title: &Title) -> String {
+        title.str().to_string()
+    }
+
+    fn person_descriptor(&self, character: &GrammaticalCharacter) -> String {
+        match character.person_descriptor() {
+            Some(descriptor) => descriptor.to_string(),
+            None => "person".to_string(),
+        }
+    }
+
+    fn conjunction(&self) -> &str {
+        "and"
+    }
+
+    fn group_descriptor(&self) -> &str {
+        "people"
+    }
+
+    fn plural(&self, noun: &str) -> String {
+        pluralize(noun)
+    }
+
+    fn possessive(&self, word: &str) -> String {
+        possessive(word)
+    }
+
+    fn indefinite_article(&self, word: &str) -> String {
+        indefinite_article(word).to_string()
+    }
+
+    /// "the", or the indefinite article that agrees with the character's person descriptor.
+    fn article(
+        &self,
+        character: &GrammaticalCharacter,
+        definite: bool,
+        _inflection: &Inflection,
+    ) -> String {
+        if definite {
+            "the".to_string()
+        } else {
+            indefinite_article(&self.person_descriptor(character)).to_string()
+        }
+    }
+}
+
+/// The pronouns of the speaker, the person spoken to, and groups.
+fn perspective_pronoun(person: ConjugatePerson, form: PronounForm) -> &'static str {
+    let [subjective, objective, possessive_determiner, possessive, reflexive] = match person {
+        ConjugatePerson::FirstSingular => ["I", "me", "my", "mine", "myself"],
+        ConjugatePerson::SecondSingular => ["you", "you", "your", "yours", "yourself"],
+        ConjugatePerson::ThirdSingular => ["they", "them", "their", "theirs", "themself"],
+        ConjugatePerson::FirstPlural => ["we", "us", "our", "ours", "ourselves"],
+        ConjugatePerson::SecondPlural => ["you", "you", "your", "yours", "yourselves"],
+        ConjugatePerson::ThirdPlural => ["they", "them", "their", "theirs", "themselves"],
+    };
+
+    match form {
+        PronounForm::Subjective => subjective,
+        PronounForm::Objective => objective,
+        PronounForm::PossessiveDeterminer => possessive_determiner,
+        PronounForm::Possessive => possessive,
+        PronounForm::Reflexive => reflexive,
+    }
+}
+
+/// Appends the possessive suffix: "Pidge's", but "Alfons'".
+fn possessive(input: &str) -> String {
+    let ends_in_s = matches!(
+        input.chars().last().map(|c| c.to_ascii_lowercase()),
+        Some('s')
+    );
+
+    let end_char = if ends_in_s { "" } else { "s" };
+
+    format!("{input}'{end_char}")
+}
+
+const IRREGULAR_PLURALS: &[(&str, &str)] = &[
+    ("man", "men"),
+    ("woman", "women"),
+    ("person", "people"),
+    ("child", "children"),
+    ("mouse", "mice"),
+    ("foot", "feet"),
+    ("tooth", "teeth"),
+    ("goose", "geese"),
+    ("ox", "oxen"),
+    ("sheep", "sheep"),
+    ("fish", "fish"),
+    ("deer", "deer"),
+];
+
+/// Pluralizes an English noun using a small set of common irregular nouns and the regular
+/// suffix rules. The capitalization of the first letter is preserved.
+fn pluralize(word: &str) -> String {
+    let lower = word.to_lowercase();
+
+    if let Some((_, plural)) = IRREGULAR_PLURALS
+        .iter()
+        .find(|(singular, _)| *singular == lower)
+    {
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+
+        return if capitalized {
+            let mut chars = plural.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        } else {
+            plural.to_string()
+        };
+    }
+
+    let ends_with = |suffix: &str| lower.ends_with(suffix);
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u');
+    let before_last = lower.chars().rev().nth(1);
+
+    if ["s", "x", "z", "ch", "sh"].into_iter().any(ends_with) {
+        format!("{word}es")
+    } else if ends_with("y") && before_last.is_some_and(|c| !is_vowel(c)) {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ends_with("ife") {
+        format!("{}ves", &word[..word.len() - 2])
+    } else if ["lf", "af", "rf"].into_iter().any(ends_with) {
+        format!("{}ves", &word[..word.len() - 1])
+    } else {
+        format!("{word}s")
+    }
+}
+
+/// Words that start with a vowel letter but are pronounced with a consonant sound.
+/// Words with the negating "un-", like "unidentified", keep the vowel sound, so only the stems
+/// of "uni-" words that start with a "you" sound are listed.
+const CONSONANT_SOUND_PREFIXES: &[&str] = &[
+    "eu", "ewe", "once", "ouija", "unicorn", "unicycl", "unif", "union", "uniq", "unit", "univers",
+    "uki", "use", "usu", "uti", "ura", "ure", "uro", "ubiq", "unan",
+];
+
+/// Whole words that start with a vowel letter but are pronounced with a consonant sound, also as
+/// the first part of compounds like "one-eyed".
+const CONSONANT_SOUND_WORDS: &[&str] = &["one"];
+
+/// Words that start with a consonant letter but are pronounced with a vowel sound.
+const VOWEL_SOUND_PREFIXES: &[&str] = &["hour", "honest", "honor", "honour", "heir"];
+
+/// Letters whose names start with a vowel sound when spelled out, e.g. "an FBI agent".
+const VOWEL_SOUND_LETTERS: &str = "AEFHILMNORSX";
+
+/// Common initialisms that are spelled out although they contain vowels. Capitalized words
+/// without vowels, like "NPC", are always spelled out, others like "EURO" are read as words.
+const INITIALISMS: &[&str] = &[
+    "AI", "AOE", "ATM", "EU", "FAQ", "FBI", "HUD", "ID", "IOU", "IQ", "LED", "MIA", "MRI", "OK",
+    "RIP", "SOS", "UFO", "UI", "UK", "UN", "US", "USA", "USB", "VIP",
+];
+
+/// Returns the English indefinite article ("a" or "an") for the word.
+fn indefinite_article(word: &str) -> &'static str {
+    let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+
+    let Some(first) = word.chars().next() else {
+        return "a";
+    };
+
+    // Initialisms are spelled out letter by letter
+    let first_word = word.split(|c: char| !c.is_alphanumeric()).next().unwrap();
+    let initialism = INITIALISMS.contains(&first_word)
+        || (first_word.len() > 1
+            && first_word.chars().all(|c| c.is_ascii_uppercase())
+            && !first_word.contains(['A', 'E', 'I', 'O', 'U', 'Y']));
+
+    if initialism {
+        return if VOWEL_SOUND_LETTERS.contains(first) {
+            "an"
+        } else {
+            "a"
+        };
+    }
+
+    // Numbers: an 8, an 11, an 18, an 80, an 11000
+    if first.is_ascii_digit() {
+        let digits: String = word.chars().take_while(char::is_ascii_digit).collect();
+        let eleven_or_eighteen =
+            (digits.starts_with("11") || digits.starts_with("18")) && digits.len() % 3 == 2;
+
+        return if first == '8' || eleven_or_eighteen {
+            "an"
+        } else {
+            "a"
+        };
+    }
+
+    let lower = word.to_lowercase();
+    let first_word = lower.split(|c: char| !c.is_alphabetic()).next();
+
+    if CONSONANT_SOUND_PREFIXES
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+        || CONSONANT_SOUND_WORDS
+            .iter()
+            .any(|word| first_word == Some(word))
+    {
+        return "a";
+    }
+
+    if VOWEL_SOUND_PREFIXES
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+    {
+        return "an";
+    }
+
+    match lower.chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u' | 'à' | 'á' | 'â' | 'ä' | 'è' | 'é' | 'ê' | 'ë')
+        | Some('ì' | 'í' | 'î' | 'ï' | 'ò' | 'ó' | 'ô' | 'ö' | 'ù' | 'ú' | 'û' | 'ü') => {
+            "an"
+        }
+        _ => "a",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pluralize_test() {
+        assert_eq!(pluralize("coin"), "coins");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("witch"), "witches");
+        assert_eq!(pluralize("ruby"), "rubies");
+        assert_eq!(pluralize("key"), "keys");
+        assert_eq!(pluralize("wolf"), "wolves");
+        assert_eq!(pluralize("knife"), "knives");
+        assert_eq!(pluralize("woman"), "women");
+        assert_eq!(pluralize("Man"), "Men");
+        assert_eq!(pluralize("sheep"), "sheep");
+    }
+
+    #[test]
+    fn possessive_test() {
+        assert_eq!(possessive("Pidge"), "Pidge's");
+        assert_eq!(possessive("Alfons"), "Alfons'");
+        assert_eq!(possessive("ALFONS"), "ALFONS'");
+    }
+
+    #[test]
+    fn article_test() {
+        for (word, article) in [
+            ("wolf", "a"),
+            ("elf", "an"),
+            ("Laru", "a"),
+            ("Alfons", "an"),
+            ("hour", "an"),
+            ("honest", "an"),
+            ("house", "a"),
+            ("unicorn", "a"),
+            ("umbrella", "an"),
+            ("European", "a"),
+            ("one-eyed", "a"),
+            ("one", "a"),
+            ("one time", "a"),
+            ("onerous", "an"),
+            ("unidentified", "an"),
+            ("uninvited", "an"),
+            ("unimportant", "an"),
+            ("uniform", "a"),
+            ("unique", "a"),
+            ("universe", "a"),
+            ("FBI", "an"),
+            ("UFO", "a"),
+            ("NPC", "an"),
+            ("HP", "an"),
+            ("EURO", "a"),
+            ("UNIT", "a"),
+            ("ORC", "an"),
+            ("MAP", "a"),
+            ("8", "an"),
+            ("18", "an"),
+            ("180", "a"),
+            ("7", "a"),
+            ("Élan", "an"),
+            ("\"elf\"", "an"),
+        ] {
+            assert_eq!(indefinite_article(word), article, "{word}");
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::{GrammaticalCharacter, Title},
+    verbs::ConjugatePerson,
+};
+
+use super::{
+    common_prefix,
+    romance::{self, PronounTable},
+    Gender, Grammar, Inflection, PronounForm,
+};
+
+/// How French agrees with characters of neutral gender and with groups of mixed gender.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FrenchNeutral {
+    /// "prêt·e", "heureux·se", "un·e"
+    #[default]
+    MiddleDot,
+    /// "prêt.e", "heureux.se", "un.e"
+    Period,
+    /// "prêts" for mixed groups, as traditionally written, while individual characters are
+    /// still "iel".
+    Masculine,
+}
+
+impl FrenchNeutral {
+    fn separator(self) -> Option<char> {
+        match self {
+            FrenchNeutral::MiddleDot => Some('·'),
+            FrenchNeutral::Period => Some('.'),
+            FrenchNeutral::Masculine => None,
+        }
+    }
+}
+
+/// French, with adjectives, articles and possessives that agree in gender and number.
+///
+/// Characters with they/them or xe/xem pronouns are referred to as "iel". Words that agree with
+/// them use the inclusive spelling of the configured [`FrenchNeutral`] strategy.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct French {
+    neutral: FrenchNeutral,
+}
+
+impl French {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given spelling for words that agree with a neutral gender.
+    pub fn with_neutral_forms(mut self, neutral: FrenchNeutral) -> Self {
+        self.neutral = neutral;
+        self
+    }
+
+    /// Picks the masculine or feminine form, or joins both for the neutral gender.
+    fn agree(&self, gender: Gender, plural: bool, masculine: String, feminine: String) -> String {
+        match (gender, self.neutral.separator()) {
+            (Gender::Feminine, _) => feminine,
+            (Gender::Neutral, Some(separator)) => {
+                inclusive_form(&masculine, &feminine, separator, plural)
+            }
+            _ => masculine,
+        }
+    }
+
+    /// Inflects a word, joining both forms for the neutral gender, see [`romance::forms`].
+    fn inflect(&self, word: &str, gender: Gender, plural: bool) -> String {
+        let (masculine, feminine) = romance::forms(word, feminine);
+
+        if plural {
+            self.agree(gender, true, pluralize(&masculine), pluralize(&feminine))
+        } else {
+            self.agree(gender, false, masculine, feminine)
+        }
+    }
+}
+
+impl Grammar for French {
+    fn locale(&self) -> &str {
+        "fr"
+    }
+
+    /// Objective pronouns default to the stressed form ("avec lui"). The accusative and dative
+    /// give the clitics "le" and "lui". Possessives agree with the possessed noun.
+    fn pronoun(
+        &self,
+        character: &GrammaticalCharacter,
+        form: PronounForm,
+        inflection: &Inflection,
+    ) -> String {
+        let person = self.conjugate_person(character);
+
+        if let PronounForm::PossessiveDeterminer | PronounForm::Possessive = form {
+            if let Some(possessive) = romance::custom_possessive(character, form) {
+                return possessive;
+            }
+
+            let column = match (inflection.noun_gender, inflection.noun_plural) {
+                (Some(Gender::Feminine), false) => 1,
+                (_, false) => 0,
+                (Some(Gender::Feminine), true) => 3,
+                (_, true) => 2,
+            };
+
+            return match form {
+                PronounForm::PossessiveDeterminer => {
+                    // There is only one plural determiner for both genders
+                    POSSESSIVE_DETERMINERS[person as usize][column.min(2)].to_string()
+                }
+                _ => POSSESSIVE_PRONOUNS[person as usize][column].to_string(),
+            };
+        }
+
+        let column = romance::pronoun_column(
+            self.gender(character),
+            self.is_plural(character),
+            self.neutral == FrenchNeutral::Masculine,
+        );
+
+        romance::personal_pronoun(&PERSONAL_PRONOUNS, column, character, form, inflection)
+    }
+
+    /// "iel" takes the singular: "iel est".
+    fn conjugate_person(&self, character: &GrammaticalCharacter) -> ConjugatePerson {
+        romance::conjugate_person(character)
+    }
+
+    fn title(&self, title: &Title) -> String {
+        match title {
+            Title::Mr => "M.".to_string(),
+            Title::Ms | Title::Mrs => "Mme".to_string(),
+            Title::Mx => "Mx".to_string(),
+            _ => title.str().to_string(),
+        }
+    }
+
+    fn person_descriptor(&self, character: &GrammaticalCharacter) -> String {
+        match character.person_descriptor() {
+            Some(descriptor) => descriptor.to_string(),
+            None => "personne".to_string(),
+        }
+    }
+
+    fn conjunction(&self) -> &str {
+        "et"
+    }
+
+    fn group_descriptor(&self) -> &str {
+        "personnes"
+    }
+
+    /// "0 pièce" and "1,5 pièce", but "2 pièces".
+    fn is_singular(&self, count: f64) -> bool {
+        count.abs() < 2.0
+    }
+
+    fn plural(&self, noun: &str) -> String {
+        pluralize(noun)
+    }
+
+    fn possessive(&self, word: &str) -> String {
+        romance::possessive(word)
+    }
+
+    /// Always "un", since French nouns don't show their gender. Articles with a `character_id`
+    /// agree with the character.
+    fn indefinite_article(&self, _word: &str) -> String {
+        "un".to_string()
+    }
+
+    fn article(
+        &self,
+        character: &GrammaticalCharacter,
+        definite: bool,
+        _inflection: &Inflection,
+    ) -> String {
+        let plural = self.is_plural(character);
+
+        let [masculine, feminine] = match (definite, plural) {
+            (true, false) => ["le", "la"],
+            (true, true) => ["les", "les"],
+            (false, false) => ["un", "une"],
+            (false, true) => ["des", "des"],
+        };
+
+        self.agree(
+            self.gender(character),
+            plural,
+            masculine.to_string(),
+            feminine.to_string(),
+        )
+    }
+
+    /// Adjectives and participles are given in their masculine singular form ("prêt"), or as
+    /// "masculine|feminine" if the feminine is irregular ("beau|belle").
+    fn adjective(
+        &self,
+        character: &GrammaticalCharacter,
+        stem: &str,
+        _inflection: &Inflection,
+    ) -> String {
+        self.inflect(stem, self.gender(character), self.is_plural(character))
+    }
+}
+
+/// "avec lui", "le", "lui".
+const PERSONAL_PRONOUNS: PronounTable = [
+    [["je", "moi", "me", "me", "me"]; 3],
+    [["tu", "toi", "te", "te", "te"]; 3],
+    [
+        ["il", "lui", "le", "lui", "se"],
+        ["elle", "elle", "la", "lui", "se"],
+        ["iel", "ellui", "lae", "lui", "se"],
+    ],
+    [["nous", "nous", "nous", "nous", "nous"]; 3],
+    [["vous", "vous", "vous", "vous", "vous"]; 3],
+    [
+        ["ils", "eux", "les", "leur", "se"],
+        ["elles", "elles", "les", "leur", "se"],
+        ["iels", "elleux", "les", "leur", "se"],
+    ],
+];
+
+/// Possessive determiners by the person of the owner. Each entry holds the masculine, feminine
+/// and plural forms.
+const POSSESSIVE_DETERMINERS: [[&str; 3]; 6] = [
+    ["mon", "ma", "mes"],
+    ["ton", "ta", "tes"],
+    ["son", "sa", "ses"],
+    ["notre", "notre", "nos"],
+    ["votre", "votre", "vos"],
+    ["leur", "leur", "leurs"],
+];
+
+/// Possessive pronouns by the person of the owner. Each entry holds the masculine, feminine,
+/// masculine plural and feminine plural forms.
+const POSSESSIVE_PRONOUNS: [[&str; 4]; 6] = [
+    ["le mien", "la mienne", "les miens", "les miennes"],
+    ["le tien", "la tienne", "les tiens", "les tiennes"],
+    ["le sien", "la sienne", "les siens", "les siennes"],
+    ["le nôtre", "la nôtre", "les nôtres", "les nôtres"],
+    ["le vôtre", "la vôtre", "les vôtres", "les vôtres"],
+    ["le leur", "la leur", "les leurs", "les leurs"],
+];
+
+/// Joins the masculine and feminine forms the way inclusive French writes them: "prêt·e",
+/// "heureux·se", and in the plural "prêt·es".
+fn inclusive_form(masculine: &str, feminine: &str, separator: char, plural: bool) -> String {
+    if masculine == feminine {
+        return masculine.to_string();
+    }
+
+    let prefix = common_prefix(masculine, feminine);
+    let mut masculine_rest = &masculine[prefix.len()..];
+    let feminine_rest = &feminine[prefix.len()..];
+
+    // The plural ending is written once, at the end
+    if plural && feminine_rest.ends_with('s') {
+        masculine_rest = masculine_rest.strip_suffix('s').unwrap_or(masculine_rest);
+    }
+
+    format!("{prefix}{masculine_rest}{separator}{feminine_rest}")
+}
+
+/// The regular feminine of a masculine adjective: "prêt" becomes "prête", "heureux" becomes
+/// "heureuse", "actif" becomes "active".
+fn feminine(masculine: &str) -> String {
+    if masculine.ends_with('e') {
+        masculine.to_string()
+    } else if let Some(stem) = masculine
+        .strip_suffix("eux")
+        .or_else(|| masculine.strip_suffix("eur"))
+    {
+        format!("{stem}euse")
+    } else if let Some(stem) = masculine.strip_suffix("if") {
+        format!("{stem}ive")
+    } else if let Some(stem) = masculine.strip_suffix("er") {
+        format!("{stem}ère")
+    } else if ["el", "en", "on"]
+        .into_iter()
+        .any(|suffix| masculine.ends_with(suffix))
+    {
+        // The final consonant doubles: "cruelle", "ancienne", "bonne"
+        let last = &masculine[masculine.len() - 1..];
+        format!("{masculine}{last}e")
+    } else {
+        format!("{masculine}e")
+    }
+}
+
+/// The regular plural: "prêt" becomes "prêts", "heureux" stays, "loyal" becomes "loyaux".
+fn pluralize(word: &str) -> String {
+    if word.ends_with(['s', 'x', 'z']) {
+        word.to_string()
+    } else if let Some(stem) = word.strip_suffix("al") {
+        format!("{stem}aux")
+    } else if word.ends_with("eau") || word.ends_with("au") || word.ends_with("eu") {
+        format!("{word}x")
+    } else {
+        format!("{word}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, character::CharacterCast, character::Pronouns, grammar::Case, verbs::Dictionary,
+        verbs::Verb, DialogMacroCompiler, RenderContext,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    fn gen_cast() -> CharacterCast {
+        let mut cast = character::tests::gen_cast();
+
+        cast.insert(
+            "allura".to_string(),
+            GrammaticalCharacter::new(
+                "Allura".to_string(),
+                Pronouns::SheHer,
+                Some(Title::Ms),
+                Some("princesse".to_string()),
+            ),
+        );
+
+        cast
+    }
+
+    fn gen_dict() -> Dictionary<'static> {
+        let mut dict = Dictionary::default();
+
+        dict.insert(
+            "être".to_string(),
+            Verb::new(
+                "être",
+                Some("être"),
+                Some("suis"),
+                Some("es"),
+                Some("est"),
+                Some("sommes"),
+                Some("êtes"),
+                Some("sont"),
+            ),
+        );
+
+        dict
+    }
+
+    #[test]
+    fn inflect_test() {
+        let french = French::new();
+
+        assert_eq!(french.inflect("prêt", Gender::Masculine, false), "prêt");
+        assert_eq!(french.inflect("prêt", Gender::Feminine, true), "prêtes");
+        assert_eq!(french.inflect("prêt", Gender::Neutral, false), "prêt·e");
+        assert_eq!(french.inflect("prêt", Gender::Neutral, true), "prêt·es");
+        assert_eq!(
+            french.inflect("heureux", Gender::Neutral, false),
+            "heureux·se"
+        );
+        assert_eq!(
+            french.inflect("heureux", Gender::Neutral, true),
+            "heureux·ses"
+        );
+        assert_eq!(french.inflect("actif", Gender::Feminine, false), "active");
+        assert_eq!(french.inflect("fier", Gender::Feminine, false), "fière");
+        assert_eq!(french.inflect("cruel", Gender::Feminine, false), "cruelle");
+        assert_eq!(french.inflect("bon", Gender::Neutral, false), "bon·ne");
+        assert_eq!(french.inflect("loyal", Gender::Masculine, true), "loyaux");
+        assert_eq!(
+            french.inflect("beau|belle", Gender::Feminine, false),
+            "belle"
+        );
+        assert_eq!(french.inflect("rapide", Gender::Neutral, false), "rapide");
+
+        let period = French::new().with_neutral_forms(FrenchNeutral::Period);
+        assert_eq!(period.inflect("prêt", Gender::Neutral, false), "prêt.e");
+
+        let masculine = French::new().with_neutral_forms(FrenchNeutral::Masculine);
+        assert_eq!(masculine.inflect("prêt", Gender::Neutral, true), "prêts");
+    }
+
+    #[test]
+    fn pronoun_test() {
+        let [pidge, _, _, hunk] = character::tests::get_characters();
+        let french = French::new();
+
+        let pronoun = |character: &GrammaticalCharacter, form, case| {
+            french.pronoun(
+                character,
+                form,
+                &Inflection {
+                    case,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(pronoun(&hunk, PronounForm::Subjective, None), "il");
+        assert_eq!(pronoun(&hunk, PronounForm::Objective, None), "lui");
+        assert_eq!(
+            pronoun(&hunk, PronounForm::Objective, Some(Case::Accusative)),
+            "le"
+        );
+        assert_eq!(pronoun(&pidge, PronounForm::Subjective, None), "iel");
+        assert_eq!(pronoun(&pidge, PronounForm::Reflexive, None), "se");
+
+        let possessive = |noun_gender, noun_plural| {
+            french.pronoun(
+                &hunk,
+                PronounForm::PossessiveDeterminer,
+                &Inflection {
+                    noun_gender: Some(noun_gender),
+                    noun_plural,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(possessive(Gender::Masculine, false), "son");
+        assert_eq!(possessive(Gender::Feminine, false), "sa");
+        assert_eq!(possessive(Gender::Feminine, true), "ses");
+    }
+
+    #[test]
+    fn french_compiler_test() -> Res {
+        let compiler = DialogMacroCompiler::new(gen_cast(), gen_dict()).with_grammar(French::new());
+
+        let source = r#"{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"pidge","type":"VerbConjugate","data":"être","mods":[]} {"character_id":"pidge","type":"Adjective","data":"prêt","mods":[]}. {"character_id":"allura","type":"TitlePlusName","data":null,"mods":[]} est {"character_id":"allura","type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"allura","type":"PersonDescriptor","data":null,"mods":[]} {"character_id":"allura","type":"Adjective","data":"courageux","mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "Iel est prêt·e. Mme Allura est une princesse courageuse."
+        );
+
+        let ctx = RenderContext::new().with_group(
+            "team".to_string(),
+            vec!["allura".to_string(), "hunk".to_string()],
+        );
+
+        let source = r#"{"character_id":"team","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"team","type":"VerbConjugate","data":"être","mods":[]} {"character_id":"team","type":"Adjective","data":"prêt","mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "Iels sont prêt·es."
+        );
+
+        Ok(())
+    }
+}
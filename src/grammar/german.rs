@@ -0,0 +1,691 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::{GrammaticalCharacter, Pronouns, Title},
+    verbs::ConjugatePerson,
+};
+
+use super::{Case, Declension, Gender, Grammar, Inflection, PronounForm};
+
+/// The pronouns German uses for characters with they/them pronouns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GermanNeutral {
+    /// dey, dey, denen, deren
+    #[default]
+    Dey,
+    /// xier, xien, xiem, xieser
+    Xier,
+    /// The character's name instead of a pronoun.
+    Name,
+}
+
+/// German, with pronouns, articles and adjectives declined by case and gender.
+///
+/// Characters with they/them pronouns use the configured [`GermanNeutral`] pronouns and xe/xem
+/// becomes xier. Articles and adjectives that agree with a neutral gender join the masculine
+/// and feminine forms with a gender star: "ein*e gute*r Freund*in".
+#[derive(Debug, Clone, Copy)]
+pub struct German {
+    neutral: GermanNeutral,
+    gender_separator: char,
+}
+
+impl Default for German {
+    fn default() -> Self {
+        Self {
+            neutral: GermanNeutral::default(),
+            gender_separator: '*',
+        }
+    }
+}
+
+impl German {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given pronouns for characters with they/them pronouns.
+    pub fn with_neutral_pronouns(mut self, neutral: GermanNeutral) -> Self {
+        self.neutral = neutral;
+        self
+    }
+
+    /// Join masculine and feminine forms with the given character instead of `*`, e.g. `:` for
+    /// "ein:e".
+    pub fn with_gender_separator(mut self, separator: char) -> Self {
+        self.gender_separator = separator;
+        self
+    }
+
+    fn pronoun_set<'c>(&self, character: &'c GrammaticalCharacter) -> Option<PronounSet<'c>> {
+        match character.perspective() {
+            Some(ConjugatePerson::FirstSingular) => return Some(ICH),
+            Some(ConjugatePerson::SecondSingular) => return Some(DU),
+            Some(ConjugatePerson::FirstPlural) => return Some(WIR),
+            Some(ConjugatePerson::SecondPlural) => return Some(IHR),
+            Some(ConjugatePerson::ThirdPlural) => return Some(SIE_PLURAL),
+            Some(ConjugatePerson::ThirdSingular) | None => {}
+        }
+
+        match character.pronouns() {
+            Pronouns::HeHim => Some(ER),
+            Pronouns::SheHer => Some(SIE),
+            Pronouns::ItIts => Some(ES),
+            Pronouns::XeXyr => Some(XIER),
+            Pronouns::TheyThem => self.neutral_set(),
+            Pronouns::Name => None,
+            // Custom pronouns are given in English forms, which only roughly map to cases
+            Pronouns::Custom {
+                subjective,
+                objective,
+                possessive_determiner,
+                possessive,
+                reflexive,
+                ..
+            } => Some(PronounSet {
+                cases: [subjective, objective, objective, possessive],
+                possessive: possessive_determiner,
+                declinable: false,
+                reflexive: [reflexive, reflexive],
+            }),
+        }
+    }
+
+    fn neutral_set(&self) -> Option<PronounSet<'static>> {
+        match self.neutral {
+            GermanNeutral::Dey => Some(DEY),
+            GermanNeutral::Xier => Some(XIER),
+            GermanNeutral::Name => None,
+        }
+    }
+
+    /// Picks the form for the gender and number from a declension table column. The neutral
+    /// gender joins the masculine and feminine forms.
+    fn agree(&self, gender: Gender, plural: bool, form: impl Fn(usize) -> String) -> String {
+        if plural {
+            return form(PLURAL);
+        }
+
+        match gender {
+            Gender::Masculine => form(MASCULINE),
+            Gender::Feminine => form(FEMININE),
+            Gender::Neuter => form(NEUTER),
+            Gender::Neutral => self.join_genders(form(MASCULINE), form(FEMININE)),
+        }
+    }
+
+    /// "ein" and "eine" become "ein*e", "guter" and "gute" become "gute*r", "der" and "die"
+    /// become "der*die".
+    fn join_genders(&self, masculine: String, feminine: String) -> String {
+        let separator = self.gender_separator;
+
+        if masculine == feminine {
+            masculine
+        } else if let Some(rest) = feminine.strip_prefix(masculine.as_str()) {
+            format!("{masculine}{separator}{rest}")
+        } else if let Some(rest) = masculine.strip_prefix(feminine.as_str()) {
+            format!("{feminine}{separator}{rest}")
+        } else {
+            format!("{masculine}{separator}{feminine}")
+        }
+    }
+}
+
+impl Grammar for German {
+    fn locale(&self) -> &str {
+        "de"
+    }
+
+    /// Subjective pronouns default to the nominative and objective pronouns to the accusative.
+    /// Possessives agree with the gender, number and case of the possessed noun: "seinem Hund".
+    fn pronoun(
+        &self,
+        character: &GrammaticalCharacter,
+        form: PronounForm,
+        inflection: &Inflection,
+    ) -> String {
+        let Some(set) = self.pronoun_set(character) else {
+            let name = character.name();
+
+            return match (form, inflection.case) {
+                (PronounForm::PossessiveDeterminer | PronounForm::Possessive, _)
+                | (_, Some(Case::Genitive)) => genitive(name),
+                (PronounForm::Reflexive, _) => "sich".to_string(),
+                (PronounForm::Subjective | PronounForm::Objective, _) => name.to_string(),
+            };
+        };
+
+        match form {
+            PronounForm::Subjective => {
+                set.cases[inflection.case.unwrap_or(Case::Nominative) as usize].to_string()
+            }
+            PronounForm::Objective => {
+                set.cases[inflection.case.unwrap_or(Case::Accusative) as usize].to_string()
+            }
+            PronounForm::Reflexive => match inflection.case {
+                Some(Case::Dative) => set.reflexive[1].to_string(),
+                _ => set.reflexive[0].to_string(),
+            },
+            PronounForm::PossessiveDeterminer | PronounForm::Possessive => {
+                if !set.declinable {
+                    return set.possessive.to_string();
+                }
+
+                let case = inflection.case.unwrap_or(Case::Nominative);
+                let gender = inflection.noun_gender.unwrap_or(Gender::Masculine);
+
+                self.agree(gender, inflection.noun_plural, |column| {
+                    // Possessive pronouns stand without a noun: "seiner", "seines"
+                    let ending = match (form, case, column) {
+                        (PronounForm::Possessive, Case::Nominative, MASCULINE) => "er",
+                        (PronounForm::Possessive, Case::Nominative | Case::Accusative, NEUTER) => {
+                            "es"
+                        }
+                        _ => POSSESSIVE_ENDINGS[case as usize][column],
+                    };
+
+                    decline(set.possessive, ending)
+                })
+            }
+        }
+    }
+
+    /// Neutral pronouns take the singular: "dey ist".
+    fn conjugate_person(&self, character: &GrammaticalCharacter) -> ConjugatePerson {
+        if let Some(person) = character.perspective() {
+            return person;
+        }
+
+        match character.pronouns() {
+            Pronouns::Custom { conjugate_case, .. } => *conjugate_case,
+            _ => ConjugatePerson::ThirdSingular,
+        }
+    }
+
+    fn title(&self, title: &Title) -> String {
+        match title {
+            Title::Mr => "Herr".to_string(),
+            Title::Ms | Title::Mrs => "Frau".to_string(),
+            _ => title.str().to_string(),
+        }
+    }
+
+    fn person_descriptor(&self, character: &GrammaticalCharacter) -> String {
+        match character.person_descriptor() {
+            Some(descriptor) => descriptor.to_string(),
+            None => "Person".to_string(),
+        }
+    }
+
+    fn conjunction(&self) -> &str {
+        "und"
+    }
+
+    fn group_descriptor(&self) -> &str {
+        "Leute"
+    }
+
+    fn plural(&self, noun: &str) -> String {
+        pluralize(noun)
+    }
+
+    fn possessive(&self, word: &str) -> String {
+        genitive(word)
+    }
+
+    /// A single word doesn't tell the gender of a German noun, so this is always "ein". Give
+    /// the article a `character_id` to make it agree with a character instead.
+    fn indefinite_article(&self, _word: &str) -> String {
+        "ein".to_string()
+    }
+
+    /// "der", "die", "das" or "ein", "eine", declined in the case of the inflection, which
+    /// defaults to the nominative.
+    fn article(
+        &self,
+        character: &GrammaticalCharacter,
+        definite: bool,
+        inflection: &Inflection,
+    ) -> String {
+        let case = inflection.case.unwrap_or(Case::Nominative) as usize;
+        let table = if definite {
+            &DEFINITE_ARTICLES
+        } else {
+            &INDEFINITE_ARTICLES
+        };
+
+        self.agree(
+            self.gender(character),
+            self.is_plural(character),
+            |column| table[case][column].to_string(),
+        )
+    }
+
+    /// Predicative adjectives stay as they are: "sie ist müde". Attributive adjectives take the
+    /// ending of their declension: "ein müder Mann".
+    fn adjective(
+        &self,
+        character: &GrammaticalCharacter,
+        stem: &str,
+        inflection: &Inflection,
+    ) -> String {
+        let Some(declension) = inflection.declension else {
+            return stem.to_string();
+        };
+
+        let case = inflection.case.unwrap_or(Case::Nominative) as usize;
+        let table = match declension {
+            Declension::Strong => &STRONG_ENDINGS,
+            Declension::Weak => &WEAK_ENDINGS,
+            Declension::Mixed => &MIXED_ENDINGS,
+        };
+
+        self.agree(
+            self.gender(character),
+            self.is_plural(character),
+            |column| decline(stem, table[case][column]),
+        )
+    }
+}
+
+/// The declined forms of a personal pronoun.
+struct PronounSet<'p> {
+    /// Nominative, accusative, dative and genitive.
+    cases: [&'p str; 4],
+    /// The stem of the possessive determiner: "sein", "ihr".
+    possessive: &'p str,
+    /// Whether the possessive determiner takes endings. "deren" doesn't.
+    declinable: bool,
+    /// The accusative and dative reflexive pronouns.
+    reflexive: [&'p str; 2],
+}
+
+const ICH: PronounSet = PronounSet {
+    cases: ["ich", "mich", "mir", "meiner"],
+    possessive: "mein",
+    declinable: true,
+    reflexive: ["mich", "mir"],
+};
+
+const DU: PronounSet = PronounSet {
+    cases: ["du", "dich", "dir", "deiner"],
+    possessive: "dein",
+    declinable: true,
+    reflexive: ["dich", "dir"],
+};
+
+const ER: PronounSet = PronounSet {
+    cases: ["er", "ihn", "ihm", "seiner"],
+    possessive: "sein",
+    declinable: true,
+    reflexive: ["sich", "sich"],
+};
+
+const SIE: PronounSet = PronounSet {
+    cases: ["sie", "sie", "ihr", "ihrer"],
+    possessive: "ihr",
+    declinable: true,
+    reflexive: ["sich", "sich"],
+};
+
+const ES: PronounSet = PronounSet {
+    cases: ["es", "es", "ihm", "seiner"],
+    possessive: "sein",
+    declinable: true,
+    reflexive: ["sich", "sich"],
+};
+
+const DEY: PronounSet = PronounSet {
+    cases: ["dey", "dey", "denen", "deren"],
+    possessive: "deren",
+    declinable: false,
+    reflexive: ["sich", "sich"],
+};
+
+const XIER: PronounSet = PronounSet {
+    cases: ["xier", "xien", "xiem", "xieser"],
+    possessive: "xies",
+    declinable: true,
+    reflexive: ["sich", "sich"],
+};
+
+const WIR: PronounSet = PronounSet {
+    cases: ["wir", "uns", "uns", "unser"],
+    possessive: "unser",
+    declinable: true,
+    reflexive: ["uns", "uns"],
+};
+
+const IHR: PronounSet = PronounSet {
+    cases: ["ihr", "euch", "euch", "euer"],
+    possessive: "euer",
+    declinable: true,
+    reflexive: ["euch", "euch"],
+};
+
+const SIE_PLURAL: PronounSet = PronounSet {
+    cases: ["sie", "sie", "ihnen", "ihrer"],
+    possessive: "ihr",
+    declinable: true,
+    reflexive: ["sich", "sich"],
+};
+
+// Columns of the declension tables. Rows are the cases in the order of `Case`.
+const MASCULINE: usize = 0;
+const FEMININE: usize = 1;
+const NEUTER: usize = 2;
+const PLURAL: usize = 3;
+
+type DeclensionTable = [[&'static str; 4]; 4];
+
+const DEFINITE_ARTICLES: DeclensionTable = [
+    ["der", "die", "das", "die"],
+    ["den", "die", "das", "die"],
+    ["dem", "der", "dem", "den"],
+    ["des", "der", "des", "der"],
+];
+
+// There is no plural indefinite article: "ein Freund", but "Freunde"
+const INDEFINITE_ARTICLES: DeclensionTable = [
+    ["ein", "eine", "ein", ""],
+    ["einen", "eine", "ein", ""],
+    ["einem", "einer", "einem", ""],
+    ["eines", "einer", "eines", ""],
+];
+
+const POSSESSIVE_ENDINGS: DeclensionTable = [
+    ["", "e", "", "e"],
+    ["en", "e", "", "e"],
+    ["em", "er", "em", "en"],
+    ["es", "er", "es", "er"],
+];
+
+const STRONG_ENDINGS: DeclensionTable = [
+    ["er", "e", "es", "e"],
+    ["en", "e", "es", "e"],
+    ["em", "er", "em", "en"],
+    ["en", "er", "en", "er"],
+];
+
+const WEAK_ENDINGS: DeclensionTable = [
+    ["e", "e", "e", "en"],
+    ["en", "e", "e", "en"],
+    ["en", "en", "en", "en"],
+    ["en", "en", "en", "en"],
+];
+
+const MIXED_ENDINGS: DeclensionTable = [
+    ["er", "e", "es", "en"],
+    ["en", "e", "es", "en"],
+    ["en", "en", "en", "en"],
+    ["en", "en", "en", "en"],
+];
+
+/// Appends a declension ending to a stem without doubling the "e": "müde" and "er" become
+/// "müder", "euer" and "e" become "eure".
+fn decline(stem: &str, ending: &str) -> String {
+    if !ending.starts_with('e') {
+        return format!("{stem}{ending}");
+    }
+
+    if stem == "euer" {
+        format!("eur{ending}")
+    } else if stem.ends_with('e') {
+        format!("{stem}{}", &ending[1..])
+    } else {
+        format!("{stem}{ending}")
+    }
+}
+
+/// The genitive of a name: "Pidges", but "Alfons'".
+fn genitive(name: &str) -> String {
+    let ends_in_sibilant = matches!(
+        name.chars().last().map(|c| c.to_ascii_lowercase()),
+        Some('s' | 'ß' | 'x' | 'z')
+    );
+
+    if ends_in_sibilant {
+        format!("{name}'")
+    } else {
+        format!("{name}s")
+    }
+}
+
+/// A rough guess at the plural of a German noun. Most German plurals are irregular, so nouns
+/// should be added to a `NounDictionary`.
+fn pluralize(noun: &str) -> String {
+    if noun.ends_with("in") {
+        format!("{noun}nen")
+    } else if noun.ends_with('e') {
+        format!("{noun}n")
+    } else if ["el", "er", "en", "chen", "lein"]
+        .into_iter()
+        .any(|suffix| noun.ends_with(suffix))
+    {
+        noun.to_string()
+    } else {
+        format!("{noun}e")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, character::CharacterCast, verbs::Dictionary, verbs::Verb, DialogMacroCompiler,
+        RenderContext,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    fn gen_cast() -> CharacterCast {
+        let mut cast = character::tests::gen_cast();
+
+        cast.insert(
+            "allura".to_string(),
+            GrammaticalCharacter::new(
+                "Allura".to_string(),
+                Pronouns::SheHer,
+                Some(Title::Ms),
+                Some("Prinzessin".to_string()),
+            ),
+        );
+
+        cast
+    }
+
+    fn gen_dict() -> Dictionary<'static> {
+        let mut dict = Dictionary::default();
+
+        dict.insert(
+            "sein".to_string(),
+            Verb::new(
+                "sein",
+                Some("sein"),
+                Some("bin"),
+                Some("bist"),
+                Some("ist"),
+                Some("sind"),
+                Some("seid"),
+                Some("sind"),
+            ),
+        );
+
+        dict
+    }
+
+    #[test]
+    fn pronoun_test() {
+        let [pidge, alfons, tupo, hunk] = character::tests::get_characters();
+        let german = German::new();
+
+        let pronoun = |character: &GrammaticalCharacter, form, case| {
+            german.pronoun(
+                character,
+                form,
+                &Inflection {
+                    case,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(pronoun(&hunk, PronounForm::Subjective, None), "er");
+        assert_eq!(pronoun(&hunk, PronounForm::Objective, None), "ihn");
+        assert_eq!(
+            pronoun(&hunk, PronounForm::Objective, Some(Case::Dative)),
+            "ihm"
+        );
+        assert_eq!(pronoun(&hunk, PronounForm::Reflexive, None), "sich");
+        assert_eq!(
+            pronoun(&pidge, PronounForm::Objective, Some(Case::Dative)),
+            "denen"
+        );
+        assert_eq!(
+            pronoun(&pidge, PronounForm::PossessiveDeterminer, None),
+            "deren"
+        );
+        assert_eq!(pronoun(&tupo, PronounForm::Objective, None), "xien");
+        assert_eq!(pronoun(&alfons, PronounForm::Objective, None), "Alfons");
+        assert_eq!(
+            pronoun(&alfons, PronounForm::PossessiveDeterminer, None),
+            "Alfons'"
+        );
+
+        let xier = German::new().with_neutral_pronouns(GermanNeutral::Xier);
+        assert_eq!(
+            xier.pronoun(&pidge, PronounForm::Subjective, &Inflection::default()),
+            "xier"
+        );
+    }
+
+    #[test]
+    fn possessive_test() {
+        let [_, _, tupo, hunk] = character::tests::get_characters();
+        let german = German::new();
+
+        let possessive = |character: &GrammaticalCharacter, form, case, noun_gender, plural| {
+            german.pronoun(
+                character,
+                form,
+                &Inflection {
+                    case: Some(case),
+                    noun_gender: Some(noun_gender),
+                    noun_plural: plural,
+                    declension: None,
+                },
+            )
+        };
+
+        use Case::*;
+        use Gender::*;
+        use PronounForm::{Possessive, PossessiveDeterminer};
+
+        assert_eq!(
+            possessive(&hunk, PossessiveDeterminer, Nominative, Masculine, false),
+            "sein"
+        );
+        assert_eq!(
+            possessive(&hunk, PossessiveDeterminer, Dative, Feminine, false),
+            "seiner"
+        );
+        assert_eq!(
+            possessive(&hunk, PossessiveDeterminer, Accusative, Neuter, true),
+            "seine"
+        );
+        assert_eq!(
+            possessive(&tupo, PossessiveDeterminer, Dative, Masculine, false),
+            "xiesem"
+        );
+        assert_eq!(
+            possessive(&hunk, Possessive, Nominative, Masculine, false),
+            "seiner"
+        );
+        assert_eq!(
+            possessive(&hunk, Possessive, Accusative, Neuter, false),
+            "seines"
+        );
+
+        let you_all =
+            GrammaticalCharacter::new("Team Voltron".to_string(), Pronouns::TheyThem, None, None)
+                .with_perspective(ConjugatePerson::SecondPlural);
+        assert_eq!(
+            possessive(&you_all, PossessiveDeterminer, Nominative, Feminine, false),
+            "eure"
+        );
+    }
+
+    #[test]
+    fn agreement_test() {
+        let [pidge, _, _, hunk] = character::tests::get_characters();
+        let german = German::new();
+
+        let inflection = |case, declension| Inflection {
+            case: Some(case),
+            declension,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            german.article(&hunk, true, &inflection(Case::Accusative, None)),
+            "den"
+        );
+        assert_eq!(
+            german.article(&pidge, true, &inflection(Case::Nominative, None)),
+            "der*die"
+        );
+        assert_eq!(
+            german.article(&pidge, false, &inflection(Case::Nominative, None)),
+            "ein*e"
+        );
+
+        let mixed = inflection(Case::Nominative, Some(Declension::Mixed));
+        assert_eq!(german.adjective(&hunk, "müde", &mixed), "müder");
+        assert_eq!(german.adjective(&pidge, "gut", &mixed), "gute*r");
+        assert_eq!(
+            german.adjective(&pidge, "gut", &inflection(Case::Nominative, None)),
+            "gut"
+        );
+
+        let colon = German::new().with_gender_separator(':');
+        assert_eq!(colon.adjective(&pidge, "gut", &mixed), "gute:r");
+    }
+
+    #[test]
+    fn words_test() {
+        assert_eq!(genitive("Pidge"), "Pidges");
+        assert_eq!(genitive("Alfons"), "Alfons'");
+        assert_eq!(pluralize("Münze"), "Münzen");
+        assert_eq!(pluralize("Freundin"), "Freundinnen");
+        assert_eq!(pluralize("Hund"), "Hunde");
+        assert_eq!(pluralize("Lehrer"), "Lehrer");
+    }
+
+    #[test]
+    fn german_compiler_test() -> Res {
+        let compiler = DialogMacroCompiler::new(gen_cast(), gen_dict()).with_grammar(German::new());
+
+        let source = r#"{"character_id":"allura","type":"TitlePlusName","data":null,"mods":[]} {"character_id":"allura","type":"VerbConjugate","data":"sein","mods":[]} {"character_id":"allura","type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"allura","type":"Adjective","data":"mutig","mods":[],"declension":"Mixed"} {"character_id":"allura","type":"PersonDescriptor","data":null,"mods":[]}. Ich gebe {"character_id":"hunk","type":"ObjectivePronoun","data":null,"mods":[],"case":"Dative"} {"character_id":"allura","type":"PossessiveDeterminer","data":null,"mods":[],"case":"Accusative","noun_gender":"Neuter"} Schwert."#;
+
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "Frau Allura ist eine mutige Prinzessin. Ich gebe ihm ihr Schwert."
+        );
+
+        let ctx = RenderContext::new()
+            .with_first_person("allura".to_string())
+            .with_group(
+                "paladins".to_string(),
+                vec!["hunk".to_string(), "pidge".to_string()],
+            );
+
+        let source = r#"{"character_id":"allura","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"allura","type":"VerbConjugate","data":"sein","mods":[]} {"character_id":"allura","type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"allura","type":"Adjective","data":"stolz","mods":[],"declension":"Mixed"} {"character_id":"allura","type":"PersonDescriptor","data":null,"mods":[]}. {"character_id":"paladins","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"paladins","type":"VerbConjugate","data":"sein","mods":[]} {"character_id":"paladins","type":"DefiniteArticle","data":null,"mods":[]} {"character_id":"paladins","type":"Adjective","data":"treu","mods":[],"declension":"Weak"} Paladine von {"character_id":"paladins","type":"NameList","data":null,"mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "Ich bin eine stolze Prinzessin. Sie sind die treuen Paladine von Hunk und Pidge."
+        );
+
+        Ok(())
+    }
+}
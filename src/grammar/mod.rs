@@ -0,0 +1,331 @@
+mod english;
+mod french;
+mod german;
+mod romance;
+mod spanish;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::{GrammaticalCharacter, Pronouns, Title},
+    verbs::{ConjugatePerson, Dictionary},
+    Error,
+};
+
+pub use english::English;
+pub use french::{French, FrenchNeutral};
+pub use german::{German, GermanNeutral};
+pub use spanish::{Spanish, SpanishNeutral};
+
+/// The pronoun a macro asks for. Which word that is depends on the language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PronounForm {
+    Subjective,
+    Objective,
+    PossessiveDeterminer,
+    Possessive,
+    Reflexive,
+}
+
+/// The grammatical case a macro is rendered in, for languages that decline pronouns, articles
+/// and adjectives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Case {
+    Nominative,
+    Accusative,
+    Dative,
+    Genitive,
+}
+
+/// The grammatical gender of a character or noun that articles and adjectives agree with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter,
+    /// Neither masculine nor feminine. Each grammar renders this with its inclusive forms.
+    Neutral,
+}
+
+/// How attributive adjectives are declined, depending on the article in front of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Declension {
+    /// No article: "guter Wein".
+    Strong,
+    /// After a definite article: "der gute Wein".
+    Weak,
+    /// After an indefinite article or possessive determiner: "ein guter Wein".
+    Mixed,
+}
+
+/// The grammatical features a macro asks for in addition to its character.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Inflection {
+    /// The case to render in. Each grammar picks a default that fits the macro if this is `None`.
+    pub case: Option<Case>,
+    /// The gender of the noun a possessive determiner belongs to: "sein Hund", "seine Katze".
+    pub noun_gender: Option<Gender>,
+    /// Whether the noun a possessive determiner belongs to is plural.
+    pub noun_plural: bool,
+    /// The declension of an attributive adjective, or `None` for a predicative adjective.
+    pub declension: Option<Declension>,
+}
+
+/// The rules of a language that the `DialogMacroCompiler` renders macros with.
+///
+/// [`English`] is the default. Other languages implement this trait and are passed to the
+/// compiler with `DialogMacroCompiler::with_grammar`.
+pub trait Grammar {
+    /// The language tag of the locale, e.g. `"en"`.
+    fn locale(&self) -> &str;
+
+    fn pronoun(
+        &self,
+        character: &GrammaticalCharacter,
+        form: PronounForm,
+        inflection: &Inflection,
+    ) -> String;
+
+    /// The grammatical gender of the character. Defaults to the gender set on the character, or
+    /// else the one its pronouns suggest.
+    fn gender(&self, character: &GrammaticalCharacter) -> Gender {
+        if let Some(gender) = character.gender() {
+            return gender;
+        }
+
+        match character.pronouns() {
+            Pronouns::HeHim => Gender::Masculine,
+            Pronouns::SheHer => Gender::Feminine,
+            Pronouns::ItIts => Gender::Neuter,
+            _ => Gender::Neutral,
+        }
+    }
+
+    /// Whether words that agree with the character take their plural forms.
+    fn is_plural(&self, character: &GrammaticalCharacter) -> bool {
+        matches!(
+            self.conjugate_person(character),
+            ConjugatePerson::FirstPlural
+                | ConjugatePerson::SecondPlural
+                | ConjugatePerson::ThirdPlural
+        )
+    }
+
+    /// The grammatical person verbs conjugate in when they agree with the character.
+    fn conjugate_person(&self, character: &GrammaticalCharacter) -> ConjugatePerson;
+
+    fn conjugate(
+        &self,
+        dictionary: &Dictionary,
+        verb: &str,
+        person: ConjugatePerson,
+    ) -> Result<String, Error> {
+        dictionary.conjugate(verb, person)
+    }
+
+    /// The person verbs conjugate in when they agree with a number.
+    fn count_person(&self, count: f64) -> ConjugatePerson {
+        if self.is_singular(count) {
+            ConjugatePerson::ThirdSingular
+        } else {
+            ConjugatePerson::ThirdPlural
+        }
+    }
+
+    fn title(&self, title: &Title) -> String;
+
+    fn title_plus_name(&self, character: &GrammaticalCharacter) -> String {
+        match character.title() {
+            Some(title) if !matches!(title, &Title::NoTitle) => {
+                format!("{} {}", self.title(title), character.name())
+            }
+            _ => character.name().to_string(),
+        }
+    }
+
+    fn person_descriptor(&self, character: &GrammaticalCharacter) -> String;
+
+    /// The conjunction that joins the last item of a list: "and".
+    fn conjunction(&self) -> &str;
+
+    /// The person descriptor of groups: "people".
+    fn group_descriptor(&self) -> &str;
+
+    /// Combines several characters into one plural subject, named after the list of its members.
+    fn group(&self, members: &[&GrammaticalCharacter]) -> GrammaticalCharacter {
+        let names: Vec<String> = members
+            .iter()
+            .map(|member| member.name().to_string())
+            .collect();
+
+        GrammaticalCharacter::new(
+            self.join_list(&names, None),
+            Pronouns::TheyThem,
+            Some(Title::NoTitle),
+            Some(self.group_descriptor().to_string()),
+        )
+        .with_gender(common_gender(self, members))
+        .with_perspective(ConjugatePerson::ThirdPlural)
+    }
+
+    /// Joins words into a natural-language list, using the default conjunction if none is given:
+    /// "Pidge, Hunk and Tupo".
+    fn join_list(&self, items: &[String], conjunction: Option<&str>) -> String {
+        let conjunction = conjunction.unwrap_or(self.conjunction());
+
+        match items {
+            [] => String::new(),
+            [single] => single.clone(),
+            [init @ .., last] => format!("{} {conjunction} {last}", init.join(", ")),
+        }
+    }
+
+    /// Whether a number takes the singular form of nouns and verbs. Defaults to exactly one:
+    /// "1 coin", but "0 coins" and "1.5 coins".
+    fn is_singular(&self, count: f64) -> bool {
+        count.abs() == 1.0
+    }
+
+    /// The regular plural of a noun.
+    fn plural(&self, noun: &str) -> String;
+
+    fn possessive(&self, word: &str) -> String;
+
+    /// The indefinite article that agrees with the word.
+    fn indefinite_article(&self, word: &str) -> String;
+
+    /// The definite or indefinite article that agrees with the character, e.g. "der" or "eine".
+    fn article(
+        &self,
+        character: &GrammaticalCharacter,
+        definite: bool,
+        inflection: &Inflection,
+    ) -> String;
+
+    /// Inflects an adjective so that it agrees with the character. Languages without adjective
+    /// agreement return the stem as is.
+    fn adjective(
+        &self,
+        _character: &GrammaticalCharacter,
+        stem: &str,
+        _inflection: &Inflection,
+    ) -> String {
+        stem.to_string()
+    }
+}
+
+/// The gender all members of a group share, or `Neutral` if they differ.
+pub(crate) fn common_gender<G: Grammar + ?Sized>(
+    grammar: &G,
+    members: &[&GrammaticalCharacter],
+) -> Gender {
+    let mut genders = members.iter().map(|member| grammar.gender(member));
+
+    match genders.next() {
+        Some(first) if genders.all(|gender| gender == first) => first,
+        _ => Gender::Neutral,
+    }
+}
+
+/// The longest start two words have in common: "cansad" for "cansado" and "cansada".
+pub(crate) fn common_prefix<'w>(a: &'w str, b: &str) -> &'w str {
+    let len = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index);
+
+    &a[..len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{character, verbs, DialogMacroCompiler};
+
+    type Res = Result<(), crate::Error>;
+
+    /// English, but with royal titles and everyone is referred to by name.
+    struct Courtly;
+
+    impl Grammar for Courtly {
+        fn locale(&self) -> &str {
+            "en-x-court"
+        }
+
+        fn pronoun(
+            &self,
+            character: &GrammaticalCharacter,
+            form: PronounForm,
+            inflection: &Inflection,
+        ) -> String {
+            match form {
+                PronounForm::Subjective | PronounForm::Objective => self.title_plus_name(character),
+                _ => English.pronoun(character, form, inflection),
+            }
+        }
+
+        fn conjugate_person(&self, _character: &GrammaticalCharacter) -> ConjugatePerson {
+            ConjugatePerson::ThirdSingular
+        }
+
+        fn title(&self, title: &Title) -> String {
+            match title {
+                Title::Mr => "Sir".to_string(),
+                _ => English.title(title),
+            }
+        }
+
+        fn person_descriptor(&self, _character: &GrammaticalCharacter) -> String {
+            "subject".to_string()
+        }
+
+        fn conjunction(&self) -> &str {
+            "and"
+        }
+
+        fn group_descriptor(&self) -> &str {
+            "subjects"
+        }
+
+        fn plural(&self, noun: &str) -> String {
+            English.plural(noun)
+        }
+
+        fn possessive(&self, word: &str) -> String {
+            English.possessive(word)
+        }
+
+        fn indefinite_article(&self, word: &str) -> String {
+            English.indefinite_article(word)
+        }
+
+        fn article(
+            &self,
+            character: &GrammaticalCharacter,
+            definite: bool,
+            inflection: &Inflection,
+        ) -> String {
+            English.article(character, definite, inflection)
+        }
+    }
+
+    #[test]
+    fn custom_grammar_test() -> Res {
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict())
+                .with_grammar(Courtly);
+
+        let source = r#"{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":[]} {"character_id":"pidge","type":"VerbConjugate","data":"to be","mods":[]} a loyal {"character_id":"pidge","type":"PersonDescriptor","data":null,"mods":[]} of {"character_id":"hunk","type":"TitlePlusName","data":null,"mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "Pidge is a loyal subject of Sir Hunk."
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! The rules Spanish and French share: the grammatical person of a character, the layout of the
+//! personal pronoun tables and the "masculine|feminine" notation of words that agree.
+
+use crate::{
+    character::{GrammaticalCharacter, Pronouns},
+    verbs::ConjugatePerson,
+};
+
+use super::{Case, Gender, Inflection, PronounForm};
+
+/// Personal pronouns by grammatical person and by masculine, feminine and neutral gender. Each
+/// entry holds the subjective, objective, accusative, dative and reflexive forms, where the
+/// objective is the form that stands alone or after prepositions.
+pub(super) type PronounTable = [[[&'static str; 5]; 3]; 6];
+
+/// Characters are referred to in the third person singular unless their custom pronouns say
+/// otherwise, which includes the neutral "elle" and "iel".
+pub(super) fn conjugate_person(character: &GrammaticalCharacter) -> ConjugatePerson {
+    if let Some(person) = character.perspective() {
+        return person;
+    }
+
+    match character.pronouns() {
+        Pronouns::Custom { conjugate_case, .. } => *conjugate_case,
+        _ => ConjugatePerson::ThirdSingular,
+    }
+}
+
+/// The column of a pronoun table. Mixed groups take the masculine column when the grammar uses
+/// the traditional agreement.
+pub(super) fn pronoun_column(gender: Gender, plural: bool, masculine_groups: bool) -> usize {
+    match gender {
+        Gender::Masculine | Gender::Neuter => 0,
+        Gender::Feminine => 1,
+        Gender::Neutral if plural && masculine_groups => 0,
+        Gender::Neutral => 2,
+    }
+}
+
+/// Looks up a subjective, objective or reflexive pronoun. Objective pronouns default to the form
+/// used after prepositions, and the accusative and dative give the clitics. Characters without
+/// pronouns are referred to by name, except in the reflexive.
+pub(super) fn personal_pronoun(
+    table: &PronounTable,
+    column: usize,
+    character: &GrammaticalCharacter,
+    form: PronounForm,
+    inflection: &Inflection,
+) -> String {
+    let index = match (form, inflection.case) {
+        (PronounForm::Reflexive, _) => 4,
+        (_, Some(Case::Nominative)) => 0,
+        (_, Some(Case::Accusative)) => 2,
+        (_, Some(Case::Dative)) => 3,
+        (PronounForm::Subjective, _) => 0,
+        _ => 1,
+    };
+
+    match (character.perspective(), character.pronouns()) {
+        (
+            None,
+            Pronouns::Custom {
+                subjective,
+                objective,
+                reflexive,
+                ..
+            },
+        ) => match index {
+            0 => subjective.to_string(),
+            4 => reflexive.to_string(),
+            _ => objective.to_string(),
+        },
+        (None, Pronouns::Name) if index != 4 => character.name().to_string(),
+        _ => table[conjugate_person(character) as usize][column][index].to_string(),
+    }
+}
+
+/// The possessives of custom pronouns, which don't agree with the possessed noun.
+pub(super) fn custom_possessive(
+    character: &GrammaticalCharacter,
+    form: PronounForm,
+) -> Option<String> {
+    let (
+        None,
+        Pronouns::Custom {
+            possessive_determiner,
+            possessive,
+            ..
+        },
+    ) = (character.perspective(), character.pronouns())
+    else {
+        return None;
+    };
+
+    match form {
+        PronounForm::PossessiveDeterminer => Some(possessive_determiner.to_string()),
+        PronounForm::Possessive => Some(possessive.to_string()),
+        _ => None,
+    }
+}
+
+/// The masculine and feminine singular of a word given in its masculine singular form, or as
+/// "masculine|feminine" for words with irregular feminine forms.
+pub(super) fn forms(word: &str, feminine: fn(&str) -> String) -> (String, String) {
+    match word.split_once('|') {
+        Some((masculine, feminine)) => (masculine.to_string(), feminine.to_string()),
+        None => (word.to_string(), feminine(word)),
+    }
+}
+
+/// The owner follows the noun, so possessives are "de Pidge" to put after it.
+pub(super) fn possessive(word: &str) -> String {
+    format!("de {word}")
+}
@@ -0,0 +1,470 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::{GrammaticalCharacter, Title},
+    verbs::ConjugatePerson,
+};
+
+use super::{
+    common_prefix,
+    romance::{self, PronounTable},
+    Gender, Grammar, Inflection, PronounForm,
+};
+
+/// How Spanish agrees with characters of neutral gender and with groups of mixed gender.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SpanishNeutral {
+    /// "cansade", "le", "une"
+    #[default]
+    E,
+    /// "cansadx", "lx", "unx"
+    X,
+    /// "cansad@", "l@", "un@"
+    At,
+    /// The masculine forms, the traditional agreement for mixed groups. Individual characters
+    /// are still referred to as "elle".
+    Masculine,
+}
+
+impl SpanishNeutral {
+    fn marker(self) -> Option<&'static str> {
+        match self {
+            SpanishNeutral::E => Some("e"),
+            SpanishNeutral::X => Some("x"),
+            SpanishNeutral::At => Some("@"),
+            SpanishNeutral::Masculine => None,
+        }
+    }
+}
+
+/// Spanish, with adjectives, articles and possessives that agree in gender and number.
+///
+/// Characters with they/them or xe/xem pronouns are referred to as "elle". Words that agree with
+/// them take the ending of the configured [`SpanishNeutral`] strategy.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Spanish {
+    neutral: SpanishNeutral,
+}
+
+impl Spanish {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given endings for words that agree with a neutral gender.
+    pub fn with_neutral_forms(mut self, neutral: SpanishNeutral) -> Self {
+        self.neutral = neutral;
+        self
+    }
+
+    /// Inflects a word for the gender and number of a character, see [`romance::forms`].
+    fn inflect(&self, word: &str, gender: Gender, plural: bool) -> String {
+        let (masculine, feminine) = romance::forms(word, feminine);
+
+        let singular = match gender {
+            Gender::Masculine | Gender::Neuter => masculine,
+            Gender::Feminine => feminine,
+            Gender::Neutral => match self.neutral.marker() {
+                Some(marker) if masculine != feminine => {
+                    // "alemán" loses its accent in "alemana", and so in "alemane"
+                    let stem = common_prefix(&feminine, &drop_final_accent(&masculine));
+                    let neutral = format!("{stem}{marker}");
+
+                    return if plural {
+                        format!("{neutral}s")
+                    } else {
+                        neutral
+                    };
+                }
+                _ => masculine,
+            },
+        };
+
+        if plural {
+            pluralize(&singular)
+        } else {
+            singular
+        }
+    }
+}
+
+impl Grammar for Spanish {
+    fn locale(&self) -> &str {
+        "es"
+    }
+
+    /// Objective pronouns default to the form used after prepositions ("con él"). The accusative
+    /// and dative give the clitics "lo" and "le". Possessives agree with the possessed noun.
+    fn pronoun(
+        &self,
+        character: &GrammaticalCharacter,
+        form: PronounForm,
+        inflection: &Inflection,
+    ) -> String {
+        let person = self.conjugate_person(character);
+
+        if let PronounForm::PossessiveDeterminer | PronounForm::Possessive = form {
+            if let Some(possessive) = romance::custom_possessive(character, form) {
+                return possessive;
+            }
+
+            let [determiner, pronoun] = match person {
+                ConjugatePerson::FirstSingular => ["mi", "mío"],
+                ConjugatePerson::SecondSingular => ["tu", "tuyo"],
+                ConjugatePerson::FirstPlural => ["nuestro", "nuestro"],
+                ConjugatePerson::SecondPlural => ["vuestro", "vuestro"],
+                ConjugatePerson::ThirdSingular | ConjugatePerson::ThirdPlural => ["su", "suyo"],
+            };
+
+            let base = match form {
+                PronounForm::PossessiveDeterminer => determiner,
+                _ => pronoun,
+            };
+
+            return self.inflect(
+                base,
+                inflection.noun_gender.unwrap_or(Gender::Masculine),
+                inflection.noun_plural,
+            );
+        }
+
+        let column = romance::pronoun_column(
+            self.gender(character),
+            self.is_plural(character),
+            self.neutral == SpanishNeutral::Masculine,
+        );
+
+        romance::personal_pronoun(&PERSONAL_PRONOUNS, column, character, form, inflection)
+    }
+
+    /// "elle" takes the singular: "elle está".
+    fn conjugate_person(&self, character: &GrammaticalCharacter) -> ConjugatePerson {
+        romance::conjugate_person(character)
+    }
+
+    fn title(&self, title: &Title) -> String {
+        match title {
+            Title::Mr => "Sr.".to_string(),
+            Title::Ms | Title::Mrs => "Sra.".to_string(),
+            Title::Mx => "Sre.".to_string(),
+            _ => title.str().to_string(),
+        }
+    }
+
+    fn person_descriptor(&self, character: &GrammaticalCharacter) -> String {
+        match character.person_descriptor() {
+            Some(descriptor) => descriptor.to_string(),
+            None => "persona".to_string(),
+        }
+    }
+
+    fn conjunction(&self) -> &str {
+        "y"
+    }
+
+    fn group_descriptor(&self) -> &str {
+        "personas"
+    }
+
+    fn plural(&self, noun: &str) -> String {
+        pluralize(noun)
+    }
+
+    fn possessive(&self, word: &str) -> String {
+        romance::possessive(word)
+    }
+
+    /// A single word doesn't tell the gender of a Spanish noun, so this is always "un". Give the
+    /// article a `character_id` to make it agree with a character instead.
+    fn indefinite_article(&self, _word: &str) -> String {
+        "un".to_string()
+    }
+
+    fn article(
+        &self,
+        character: &GrammaticalCharacter,
+        definite: bool,
+        _inflection: &Inflection,
+    ) -> String {
+        let (forms, neutral_stem) = if definite {
+            (["el", "la", "los", "las"], "l")
+        } else {
+            (["un", "una", "unos", "unas"], "un")
+        };
+
+        let plural = self.is_plural(character);
+        let [masculine, feminine] = if plural {
+            [forms[2], forms[3]]
+        } else {
+            [forms[0], forms[1]]
+        };
+
+        match (self.gender(character), self.neutral.marker()) {
+            (Gender::Feminine, _) => feminine.to_string(),
+            (Gender::Neutral, Some(marker)) if plural => format!("{neutral_stem}{marker}s"),
+            (Gender::Neutral, Some(marker)) => format!("{neutral_stem}{marker}"),
+            _ => masculine.to_string(),
+        }
+    }
+
+    /// Adjectives and participles are given in their masculine singular form ("cansado"), or as
+    /// "masculine|feminine" if the feminine is irregular.
+    fn adjective(
+        &self,
+        character: &GrammaticalCharacter,
+        stem: &str,
+        _inflection: &Inflection,
+    ) -> String {
+        self.inflect(stem, self.gender(character), self.is_plural(character))
+    }
+}
+
+/// "con él", "lo", "le".
+const PERSONAL_PRONOUNS: PronounTable = [
+    [["yo", "mí", "me", "me", "me"]; 3],
+    [["tú", "ti", "te", "te", "te"]; 3],
+    [
+        ["él", "él", "lo", "le", "se"],
+        ["ella", "ella", "la", "le", "se"],
+        ["elle", "elle", "le", "le", "se"],
+    ],
+    [
+        ["nosotros", "nosotros", "nos", "nos", "nos"],
+        ["nosotras", "nosotras", "nos", "nos", "nos"],
+        ["nosotres", "nosotres", "nos", "nos", "nos"],
+    ],
+    [
+        ["vosotros", "vosotros", "os", "os", "os"],
+        ["vosotras", "vosotras", "os", "os", "os"],
+        ["vosotres", "vosotres", "os", "os", "os"],
+    ],
+    [
+        ["ellos", "ellos", "los", "les", "se"],
+        ["ellas", "ellas", "las", "les", "se"],
+        ["elles", "elles", "les", "les", "se"],
+    ],
+];
+
+/// The regular feminine of a masculine adjective: "cansado" becomes "cansada", "trabajador"
+/// becomes "trabajadora". Other adjectives are the same in both genders.
+fn feminine(masculine: &str) -> String {
+    if let Some(stem) = masculine.strip_suffix('o') {
+        format!("{stem}a")
+    } else if masculine.ends_with("or") {
+        format!("{masculine}a")
+    } else if ["ón", "án", "ín", "és"]
+        .into_iter()
+        .any(|suffix| masculine.ends_with(suffix))
+    {
+        format!("{}a", drop_final_accent(masculine))
+    } else {
+        masculine.to_string()
+    }
+}
+
+/// The regular plural: "cansada" becomes "cansadas", "feliz" becomes "felices", "camión" becomes
+/// "camiones".
+fn pluralize(word: &str) -> String {
+    let Some(last) = word.chars().last() else {
+        return String::new();
+    };
+
+    let accented_ending = drop_final_accent(word);
+
+    if "aeiouáéó".contains(last) {
+        format!("{word}s")
+    } else if let Some(stem) = word.strip_suffix('z') {
+        format!("{stem}ces")
+    } else if accented_ending != word {
+        format!("{accented_ending}es")
+    } else if matches!(last, 's' | 'x') {
+        word.to_string()
+    } else {
+        format!("{word}es")
+    }
+}
+
+/// Removes the accent of a stressed final syllable that ends in "n" or "s", since suffixes
+/// move the stress: "camión" and "camiones".
+fn drop_final_accent(word: &str) -> String {
+    let mut chars: Vec<char> = word.chars().collect();
+
+    if let [.., vowel, 'n' | 's'] = chars.as_mut_slice() {
+        *vowel = match *vowel {
+            'á' => 'a',
+            'é' => 'e',
+            'í' => 'i',
+            'ó' => 'o',
+            'ú' => 'u',
+            other => other,
+        };
+    }
+
+    chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        character, character::CharacterCast, character::Pronouns, grammar::Case, verbs::Dictionary,
+        verbs::Verb, DialogMacroCompiler, RenderContext,
+    };
+
+    type Res = Result<(), crate::Error>;
+
+    fn gen_cast() -> CharacterCast {
+        let mut cast = character::tests::gen_cast();
+
+        cast.insert(
+            "allura".to_string(),
+            GrammaticalCharacter::new(
+                "Allura".to_string(),
+                Pronouns::SheHer,
+                Some(Title::Ms),
+                Some("princesa".to_string()),
+            ),
+        );
+
+        cast
+    }
+
+    fn gen_dict() -> Dictionary<'static> {
+        let mut dict = Dictionary::default();
+
+        dict.insert(
+            "estar".to_string(),
+            Verb::new(
+                "estar",
+                Some("estar"),
+                Some("estoy"),
+                Some("estás"),
+                Some("está"),
+                Some("estamos"),
+                Some("estáis"),
+                Some("están"),
+            ),
+        );
+
+        dict
+    }
+
+    #[test]
+    fn inflect_test() {
+        let spanish = Spanish::new();
+
+        assert_eq!(
+            spanish.inflect("cansado", Gender::Masculine, false),
+            "cansado"
+        );
+        assert_eq!(
+            spanish.inflect("cansado", Gender::Feminine, true),
+            "cansadas"
+        );
+        assert_eq!(
+            spanish.inflect("cansado", Gender::Neutral, false),
+            "cansade"
+        );
+        assert_eq!(
+            spanish.inflect("cansado", Gender::Neutral, true),
+            "cansades"
+        );
+        assert_eq!(
+            spanish.inflect("trabajador", Gender::Feminine, false),
+            "trabajadora"
+        );
+        assert_eq!(spanish.inflect("feliz", Gender::Neutral, true), "felices");
+        assert_eq!(spanish.inflect("alemán", Gender::Neutral, false), "alemane");
+        assert_eq!(
+            spanish.inflect("francés", Gender::Neutral, false),
+            "francese"
+        );
+        assert_eq!(
+            spanish.inflect("alemán", Gender::Masculine, true),
+            "alemanes"
+        );
+        assert_eq!(
+            spanish.inflect("mejor|mejor", Gender::Feminine, false),
+            "mejor"
+        );
+
+        let x = Spanish::new().with_neutral_forms(SpanishNeutral::X);
+        assert_eq!(x.inflect("cansado", Gender::Neutral, true), "cansadxs");
+
+        let masculine = Spanish::new().with_neutral_forms(SpanishNeutral::Masculine);
+        assert_eq!(
+            masculine.inflect("cansado", Gender::Neutral, true),
+            "cansados"
+        );
+    }
+
+    #[test]
+    fn pronoun_test() {
+        let [pidge, alfons, _, hunk] = character::tests::get_characters();
+        let spanish = Spanish::new();
+
+        let pronoun = |character: &GrammaticalCharacter, form, case| {
+            spanish.pronoun(
+                character,
+                form,
+                &Inflection {
+                    case,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(pronoun(&hunk, PronounForm::Subjective, None), "él");
+        assert_eq!(
+            pronoun(&hunk, PronounForm::Objective, Some(Case::Accusative)),
+            "lo"
+        );
+        assert_eq!(pronoun(&pidge, PronounForm::Subjective, None), "elle");
+        assert_eq!(
+            pronoun(&pidge, PronounForm::Objective, Some(Case::Dative)),
+            "le"
+        );
+        assert_eq!(pronoun(&alfons, PronounForm::Objective, None), "Alfons");
+        assert_eq!(pronoun(&alfons, PronounForm::Reflexive, None), "se");
+
+        let possessive = spanish.pronoun(
+            &hunk,
+            PronounForm::Possessive,
+            &Inflection {
+                noun_gender: Some(Gender::Feminine),
+                noun_plural: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(possessive, "suyas");
+    }
+
+    #[test]
+    fn spanish_compiler_test() -> Res {
+        let compiler =
+            DialogMacroCompiler::new(gen_cast(), gen_dict()).with_grammar(Spanish::new());
+
+        let source = r#"{"character_id":"pidge","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"pidge","type":"VerbConjugate","data":"estar","mods":[]} {"character_id":"pidge","type":"Adjective","data":"cansado","mods":[]}. {"character_id":"allura","type":"TitlePlusName","data":null,"mods":[]} es {"character_id":"allura","type":"IndefiniteArticle","data":null,"mods":[]} {"character_id":"allura","type":"PersonDescriptor","data":null,"mods":[]} {"character_id":"allura","type":"Adjective","data":"valiente","mods":[]}."#;
+
+        assert_eq!(
+            compiler.parse_and_compile(source)?,
+            "Elle está cansade. Sra. Allura es una princesa valiente."
+        );
+
+        let ctx = RenderContext::new()
+            .with_first_person("allura".to_string())
+            .with_group(
+                "team".to_string(),
+                vec!["allura".to_string(), "hunk".to_string()],
+            );
+
+        let source = r#"{"character_id":"allura","type":"SubjectivePronoun","data":null,"mods":["Capitalized"]} {"character_id":"allura","type":"VerbConjugate","data":"estar","mods":[]} {"character_id":"allura","type":"Adjective","data":"listo","mods":[]}. ¿{"character_id":"team","type":"VerbConjugate","data":"estar","mods":["Capitalized"]} {"character_id":"team","type":"SubjectivePronoun","data":null,"mods":[]} {"character_id":"team","type":"Adjective","data":"listo","mods":[]}?"#;
+
+        assert_eq!(
+            compiler.parse_and_compile_with(source, &ctx)?,
+            "Yo estoy lista. ¿Estamos nosotres listes?"
+        );
+
+        Ok(())
+    }
+}
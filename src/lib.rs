@@ -1,13 +1,53 @@
 mod character;
+mod context;
+mod convert;
 mod dialog_parser;
+mod document;
+#[cfg(feature = "fluent")]
+mod fluent;
+mod format;
+mod gettext;
+mod grammar;
+mod mods;
+mod nouns;
+mod runner;
+#[cfg(feature = "schema")]
+mod schema;
+mod spreadsheet;
+mod suggest;
+mod template;
+mod variables;
 mod verbs;
 
 use std::fmt::Display;
 
 // TODO: expose API
 
-pub use character::{CharacterCast, GrammaticalCharacter, Pronouns, Title};
-pub use dialog_parser::{DialogMacro, DialogMacroCompiler};
+pub use character::{CharacterCast, CharacterLocalization, GrammaticalCharacter, Pronouns, Title};
+pub use context::RenderContext;
+pub use convert::{from_ink, from_twine, from_yarn, Conversion, ConversionWarning, WarningKind};
+pub use dialog_parser::{
+    DialogMacro, DialogMacroCompiler, DialogMacroMod, DialogMacroType, Fallback, FallbackKind,
+    MacroHandler, TextFilter,
+};
+pub use document::{
+    DialogChoice, DialogLine, DialogNode, NodeExit, TextId, XyrDocument, END_LABEL, START_LABEL,
+};
+#[cfg(feature = "fluent")]
+pub use fluent::{character_args, set_character_args, FluentMacro};
+pub use format::{migrate_cast, migrate_dictionary, migrate_macro, migrate_nouns, FORMAT_VERSION};
+pub use gettext::{extract_pot, Translations};
+pub use grammar::{
+    Case, Declension, English, French, FrenchNeutral, Gender, German, GermanNeutral, Grammar,
+    Inflection, PronounForm, Spanish, SpanishNeutral,
+};
+pub use nouns::{Noun, NounDictionary};
+pub use runner::{DialogEvent, DialogRunner, RenderedLine};
+#[cfg(feature = "schema")]
+pub use schema::schemas;
+pub use spreadsheet::{export_csv, import_csv};
+pub use template::{Segment, Template};
+pub use variables::{Value, Variables};
 pub use verbs::{ConjugatePerson, Dictionary, Verb};
 
 //--------------------------------------------------
@@ -17,22 +57,66 @@ pub use verbs::{ConjugatePerson, Dictionary, Verb};
 #[derive(Debug)]
 pub enum Error {
     Serde(serde_json::Error),
-    UnknownVerbKey,
+    UnknownVerbKey(UnknownName),
     UndefinedVerbCase,
     MissingMacroData,
-    UnknownCharacterIdentifier,
+    UnknownCharacterIdentifier(UnknownName),
+    MissingCharacterIdentifier,
+    UnknownVariable,
+    MissingMacroCount,
+    NonNumericCount,
+    MissingArticleNoun,
+    UnknownMacroType(UnknownName),
+    UnknownMod(UnknownName),
     UnmatchedClosingBrace,
+    MalformedDocument,
+    DuplicateNodeLabel,
+    UnknownNodeLabel,
+    EmptyNodeCycle,
+    InvalidChoice,
+    UnknownTextId,
+    MalformedPo,
+    MissingPlaceholder,
+    UnknownPlaceholder,
+    UnknownFluentMessage,
+    FluentFormat,
+    UnsupportedFluentSyntax,
+    MalformedCsv,
+    UnsupportedVersion,
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Serde(serde_error) => Display::fmt(serde_error, f),
-            Error::UnknownVerbKey => f.write_str("unknown verb key"),
+            Error::UnknownVerbKey(key) => write!(f, "unknown verb key {key}"),
             Error::UndefinedVerbCase => f.write_str("undefined verb case"),
             Error::MissingMacroData => f.write_str("macro misses data attribute"),
-            Error::UnknownCharacterIdentifier => f.write_str("unknown character identifier"),
+            Error::UnknownCharacterIdentifier(id) => write!(f, "unknown character identifier {id}"),
+            Error::MissingCharacterIdentifier => f.write_str("macro misses character identifier"),
+            Error::UnknownVariable => f.write_str("unknown variable"),
+            Error::MissingMacroCount => f.write_str("macro misses count attribute"),
+            Error::NonNumericCount => f.write_str("count variable is not a number"),
+            Error::MissingArticleNoun => f.write_str("no word follows the indefinite article"),
+            Error::UnknownMacroType(name) => write!(f, "unknown macro type {name}"),
+            Error::UnknownMod(name) => write!(f, "unknown macro mod {name}"),
             Error::UnmatchedClosingBrace => f.write_str("unmatched closing brace"),
+            Error::MalformedDocument => f.write_str("malformed dialog document"),
+            Error::DuplicateNodeLabel => f.write_str("duplicate node label"),
+            Error::UnknownNodeLabel => f.write_str("unknown node label"),
+            Error::EmptyNodeCycle => f.write_str("dialog nodes loop without any lines"),
+            Error::InvalidChoice => f.write_str("invalid dialog choice"),
+            Error::UnknownTextId => f.write_str("unknown dialog text id"),
+            Error::MalformedPo => f.write_str("malformed PO file"),
+            Error::MissingPlaceholder => f.write_str("translation misses a macro placeholder"),
+            Error::UnknownPlaceholder => {
+                f.write_str("translation has an unknown macro placeholder")
+            }
+            Error::UnknownFluentMessage => f.write_str("unknown fluent message"),
+            Error::FluentFormat => f.write_str("fluent message failed to format"),
+            Error::UnsupportedFluentSyntax => f.write_str("fluent message has no xyr equivalent"),
+            Error::MalformedCsv => f.write_str("malformed CSV file"),
+            Error::UnsupportedVersion => f.write_str("unsupported format version"),
         }
     }
 }
@@ -59,3 +143,40 @@ impl From<serde_json::Error> for Error {
         Self::Serde(source)
     }
 }
+
+/// A character, verb, macro type or mod that doesn't exist, with the closest existing name as a
+/// hint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownName {
+    name: String,
+    suggestion: Option<String>,
+}
+
+impl UnknownName {
+    pub(crate) fn new<'c>(name: &str, candidates: impl IntoIterator<Item = &'c str>) -> Self {
+        Self {
+            name: name.to_string(),
+            suggestion: suggest::closest(name, candidates).map(str::to_string),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The existing name that is closest to the unknown one, if any is close enough.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
+}
+
+impl Display for UnknownName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}`", self.name)?;
+
+        match &self.suggestion {
+            Some(suggestion) => write!(f, ", did you mean `{suggestion}`?"),
+            None => Ok(()),
+        }
+    }
+}
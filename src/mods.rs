@@ -0,0 +1,139 @@
+use smallvec::{smallvec, SmallVec};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Uppercases the first character and leaves the rest as is.
+pub(crate) fn capitalize(input: &str) -> String {
+    let mut first = true;
+
+    input
+        .chars()
+        .flat_map(|x| {
+            // Unicode to_uppercase may turn one character into multiple ones. For this reason
+            // we need to provide a vector for the ToUppercase iterator to write into.
+            // Think e.g. ß -> SS.
+            // Use SmallVec to avoid heap allocations.
+            let y: SmallVec<[char; 2]> = if first {
+                x.to_uppercase().collect()
+            } else {
+                smallvec![x]
+            };
+
+            first = false;
+
+            y
+        })
+        .collect()
+}
+
+/// Uppercases the first user-perceived character of every word, including every part of
+/// hyphenated words.
+pub(crate) fn title_case(input: &str) -> String {
+    let mut word_start = true;
+    let mut output = String::with_capacity(input.len());
+
+    for grapheme in input.graphemes(true) {
+        if word_start {
+            output.extend(grapheme.chars().flat_map(char::to_uppercase));
+        } else {
+            output.push_str(grapheme);
+        }
+
+        word_start = grapheme.chars().all(char::is_whitespace) || grapheme == "-";
+    }
+
+    output
+}
+
+/// Keeps at most `len` user-perceived characters, the grapheme clusters of Unicode, so letters
+/// keep their marks and emoji sequences stay whole.
+pub(crate) fn truncate(input: &str, len: usize) -> String {
+    input.graphemes(true).take(len).collect()
+}
+
+/// Repeats the start of the first word up to its first vowel: "They" -> "Th-they".
+///
+/// User-perceived characters are judged by their first character, so letters keep their marks.
+pub(crate) fn stutter(input: &str) -> String {
+    let is_vowel = |c: char| {
+        c.to_lowercase()
+            .any(|c| "aeiouyàáâãäåæèéêëìíîïòóôõöøœùúûüýÿ".contains(c))
+    };
+    let base = |grapheme: &str| grapheme.chars().next().unwrap_or_default();
+
+    let Some(first) = input
+        .graphemes(true)
+        .next()
+        .filter(|grapheme| base(grapheme).is_alphabetic())
+    else {
+        return input.to_string();
+    };
+
+    let first_word: Vec<&str> = input
+        .graphemes(true)
+        .take_while(|grapheme| base(grapheme).is_alphabetic())
+        .collect();
+
+    // The consonants up to the first vowel, or only the first letter if the word starts with one
+    let stutter: String = if is_vowel(base(first)) {
+        first.to_string()
+    } else {
+        first_word
+            .iter()
+            .take_while(|grapheme| !is_vowel(base(grapheme)))
+            .copied()
+            .collect()
+    };
+
+    let shouting = !first_word
+        .iter()
+        .flat_map(|grapheme| grapheme.chars())
+        .any(char::is_lowercase);
+
+    // The repeated word is not capitalized, unless all of it is, like "STOP" or "I"
+    let repeated = if shouting {
+        input.to_string()
+    } else {
+        format!("{}{}", first.to_lowercase(), &input[first.len()..])
+    };
+
+    format!("{stutter}-{repeated}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_test() {
+        assert_eq!(capitalize("ßa"), "SSa");
+
+        assert_eq!(title_case("the one-eyed king"), "The One-Eyed King");
+        assert_eq!(title_case("ßtraße"), "SStraße");
+
+        assert_eq!(truncate("Alfons", 3), "Alf");
+        assert_eq!(truncate("Ærøskøbing", 4), "Ærøs");
+        assert_eq!(truncate("Tupo", 10), "Tupo");
+        assert_eq!(truncate("e\u{301}tude", 2), "e\u{301}t");
+        assert_eq!(truncate("👩\u{200D}🚀👩\u{200D}🚀", 1), "👩\u{200D}🚀");
+        assert_eq!(truncate("👍🏽👍", 1), "👍🏽");
+        assert_eq!(truncate("🇩🇪🇫🇷🇮", 2), "🇩🇪🇫🇷");
+        assert_eq!(truncate("❤\u{FE0F}!", 1), "❤\u{FE0F}");
+        assert_eq!(truncate("1\u{FE0F}\u{20E3}2", 1), "1\u{FE0F}\u{20E3}");
+        assert_eq!(truncate("\r\nHi", 1), "\r\n");
+        assert_eq!(
+            truncate("\u{1100}\u{1161}\u{11A8}\u{1100}", 1),
+            "\u{1100}\u{1161}\u{11A8}"
+        );
+        assert_eq!(truncate("किताब", 2), "किता");
+        assert_eq!(title_case("e\u{301}tude a\u{301}"), "E\u{301}tude A\u{301}");
+
+        assert_eq!(stutter("They"), "Th-they");
+        assert_eq!(stutter("they are"), "th-they are");
+        assert_eq!(stutter("I"), "I-I");
+        assert_eq!(stutter("elf"), "e-elf");
+        assert_eq!(stutter("STOP"), "ST-STOP");
+        assert_eq!(stutter("Éric"), "É-éric");
+        assert_eq!(stutter("E\u{301}ric"), "E\u{301}-e\u{301}ric");
+        assert_eq!(stutter("42"), "42");
+    }
+}
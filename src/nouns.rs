@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{format, grammar::Grammar, Error};
+
+/// A table of irregular nouns. Nouns that are not in the table are inflected using the regular
+/// plural rules of the grammar.
+#[derive(Debug, Default, Serialize)]
+pub struct NounDictionary<'a> {
+    #[serde(rename = "nouns")]
+    map: HashMap<String, Noun<'a>>,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for NounDictionary<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// The current layout, which also reads the `"map"` of version 0.
+        #[derive(Deserialize)]
+        struct Layout<'a> {
+            #[serde(default, rename = "version")]
+            _version: format::Version,
+            #[serde(borrow, alias = "map")]
+            nouns: HashMap<String, Noun<'a>>,
+        }
+
+        Ok(Self {
+            map: Layout::deserialize(deserializer)?.nouns,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Noun<'a> {
+    singular: &'a str,
+    plural: &'a str,
+}
+
+impl<'a> NounDictionary<'a> {
+    /// Reads a noun dictionary file of any format version.
+    pub fn from_json(src: &'a str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(src)?)
+    }
+
+    /// Writes the noun dictionary in the current format version.
+    pub fn to_json(&self) -> Result<String, Error> {
+        format::to_json(self)
+    }
+
+    pub fn insert(&mut self, key: String, noun: Noun<'a>) -> Option<Noun<'a>> {
+        self.map.insert(key, noun)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Noun<'a>> {
+        self.map.remove(key)
+    }
+
+    /// Returns the form of the noun that agrees with `count`.
+    pub fn inflect(&self, key: &str, count: f64, grammar: &dyn Grammar) -> String {
+        if grammar.is_singular(count) {
+            match self.map.get(key) {
+                Some(noun) => noun.singular.to_string(),
+                None => key.to_string(),
+            }
+        } else {
+            self.plural(key, grammar)
+        }
+    }
+
+    /// Returns the plural of the noun, preferring the table over the regular rules of the
+    /// grammar.
+    pub fn plural(&self, key: &str, grammar: &dyn Grammar) -> String {
+        match self.map.get(key) {
+            Some(noun) => noun.plural.to_string(),
+            None => grammar.plural(key),
+        }
+    }
+}
+
+impl<'a> Noun<'a> {
+    pub fn new(singular: &'a str, plural: &'a str) -> Self {
+        Self { singular, plural }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn gen_nouns() -> NounDictionary<'static> {
+        let mut nouns = NounDictionary::default();
+
+        nouns.insert("thief".to_string(), Noun::new("thief", "thieves"));
+        nouns.insert("cactus".to_string(), Noun::new("cactus", "cacti"));
+
+        nouns
+    }
+
+    #[test]
+    fn inflect_test() {
+        let nouns = gen_nouns();
+        let english = &crate::grammar::English;
+
+        assert_eq!(nouns.inflect("coin", 1.0, english), "coin");
+        assert_eq!(nouns.inflect("coin", 0.0, english), "coins");
+        assert_eq!(nouns.inflect("coin", 1.5, english), "coins");
+        assert_eq!(nouns.inflect("thief", 1.0, english), "thief");
+        assert_eq!(nouns.inflect("thief", 2.0, english), "thieves");
+        assert_eq!(nouns.inflect("cactus", 3.0, english), "cacti");
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use crate::{
+    context::RenderContext,
+    dialog_parser::DialogMacroCompiler,
+    document::{NodeExit, TextId, XyrDocument, END_LABEL},
+    template::Template,
+    Error,
+};
+
+/// Walks the node graph of a [`XyrDocument`] and renders every line and choice through a
+/// [`DialogMacroCompiler`].
+pub struct DialogRunner<'r, 'a> {
+    compiler: &'r DialogMacroCompiler<'a>,
+    document: &'r XyrDocument,
+    ctx: RenderContext<'r>,
+    state: RunnerState,
+    precompiled: Option<Precompiled<'r>>,
+}
+
+/// The templates `pronouner-build` generated for the file of the document.
+struct Precompiled<'r> {
+    file: &'r str,
+    templates: &'r HashMap<&'r str, Template<'r>>,
+    speakers: &'r HashMap<&'r str, Template<'r>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunnerState {
+    Running { node: usize, line: usize },
+    AwaitingChoice { node: usize },
+    Finished,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogEvent {
+    Line(RenderedLine),
+    /// The player has to pick one of the choices using [`DialogRunner::choose`].
+    Choices(Vec<String>),
+    End,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedLine {
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+impl<'r, 'a> DialogRunner<'r, 'a> {
+    /// Creates a runner that starts at the first node of the document.
+    pub fn new(compiler: &'r DialogMacroCompiler<'a>, document: &'r XyrDocument) -> Self {
+        let state = if document.nodes().is_empty() {
+            RunnerState::Finished
+        } else {
+            RunnerState::Running { node: 0, line: 0 }
+        };
+
+        Self {
+            compiler,
+            document,
+            ctx: RenderContext::default(),
+            state,
+            precompiled: None,
+        }
+    }
+
+    /// Creates a runner that starts at the node with the given label.
+    pub fn starting_at(
+        compiler: &'r DialogMacroCompiler<'a>,
+        document: &'r XyrDocument,
+        label: &str,
+    ) -> Result<Self, Error> {
+        let Some(node) = document.index_of(label) else {
+            return Err(Error::UnknownNodeLabel);
+        };
+
+        Ok(Self {
+            compiler,
+            document,
+            ctx: RenderContext::default(),
+            state: RunnerState::Running { node, line: 0 },
+            precompiled: None,
+        })
+    }
+
+    /// Renders all following lines and choices with the given context.
+    pub fn with_context(mut self, ctx: RenderContext<'r>) -> Self {
+        self.ctx = ctx;
+        self
+    }
+
+    pub fn set_context(&mut self, ctx: RenderContext<'r>) {
+        self.ctx = ctx;
+    }
+
+    /// Renders lines, choices and speakers from the `templates()` and `speakers()` generated by
+    /// `pronouner-build`, instead of parsing their text. `file` is the path of the document in
+    /// the precompiled directory, without its extension. Texts without a template are parsed.
+    pub fn with_templates(
+        mut self,
+        file: &'r str,
+        templates: &'r HashMap<&'r str, Template<'r>>,
+        speakers: &'r HashMap<&'r str, Template<'r>>,
+    ) -> Self {
+        self.precompiled = Some(Precompiled {
+            file,
+            templates,
+            speakers,
+        });
+        self
+    }
+
+    /// The label of the node the runner is currently in, or `None` once the conversation ended.
+    pub fn current_node(&self) -> Option<&str> {
+        match self.state {
+            RunnerState::Running { node, .. } | RunnerState::AwaitingChoice { node } => {
+                self.document.node_at(node).map(|node| node.label())
+            }
+            RunnerState::Finished => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state == RunnerState::Finished
+    }
+
+    /// Advances the conversation and returns the next event.
+    ///
+    /// While the runner waits for the player to choose, this keeps returning the same choices.
+    pub fn advance(&mut self) -> Result<DialogEvent, Error> {
+        while let RunnerState::Running { node, line } = self.state {
+            let current = self.document.node_at(node).unwrap();
+
+            if line < current.lines().len() {
+                self.state = RunnerState::Running {
+                    node,
+                    line: line + 1,
+                };
+
+                return self.render_line(node, line).map(DialogEvent::Line);
+            }
+
+            self.state = match current.exit() {
+                NodeExit::FallThrough if node + 1 < self.document.nodes().len() => {
+                    RunnerState::Running {
+                        node: node + 1,
+                        line: 0,
+                    }
+                }
+                NodeExit::FallThrough | NodeExit::End => RunnerState::Finished,
+                NodeExit::Jump(target) => self.jump(target),
+                NodeExit::Choices(_) => RunnerState::AwaitingChoice { node },
+            };
+        }
+
+        match self.state {
+            RunnerState::AwaitingChoice { node } => {
+                let current = self.document.node_at(node).unwrap();
+                let NodeExit::Choices(choices) = current.exit() else {
+                    unreachable!("runner awaits a choice in a node without choices");
+                };
+
+                let choices = choices
+                    .iter()
+                    .enumerate()
+                    .map(|(index, choice)| {
+                        let id = TextId::Choice {
+                            node: current.label().to_string(),
+                            index,
+                        };
+                        self.render(choice.text(), self.template(&id, false))
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Ok(DialogEvent::Choices(choices))
+            }
+            _ => Ok(DialogEvent::End),
+        }
+    }
+
+    /// Picks the choice with the given index and continues at its target node.
+    pub fn choose(&mut self, index: usize) -> Result<(), Error> {
+        let RunnerState::AwaitingChoice { node } = self.state else {
+            return Err(Error::InvalidChoice);
+        };
+
+        let NodeExit::Choices(choices) = self.document.node_at(node).unwrap().exit() else {
+            return Err(Error::InvalidChoice);
+        };
+
+        let Some(choice) = choices.get(index) else {
+            return Err(Error::InvalidChoice);
+        };
+
+        self.state = self.jump(choice.target());
+
+        Ok(())
+    }
+
+    fn jump(&self, target: &str) -> RunnerState {
+        if target == END_LABEL {
+            return RunnerState::Finished;
+        }
+
+        // Targets are validated when the document is parsed
+        match self.document.index_of(target) {
+            Some(node) => RunnerState::Running { node, line: 0 },
+            None => RunnerState::Finished,
+        }
+    }
+
+    fn render_line(&self, node: usize, index: usize) -> Result<RenderedLine, Error> {
+        let node = self.document.node_at(node).unwrap();
+        let line = &node.lines()[index];
+        let id = TextId::Line {
+            node: node.label().to_string(),
+            index,
+        };
+
+        let speaker = match line.speaker() {
+            Some(speaker) => Some(self.render(speaker, self.template(&id, true))?),
+            None => None,
+        };
+
+        Ok(RenderedLine {
+            speaker,
+            text: self.render(line.text(), self.template(&id, false))?,
+        })
+    }
+
+    /// The precompiled template of a text, or of the speaker of a line.
+    fn template(&self, id: &TextId, speaker: bool) -> Option<&Template<'r>> {
+        let precompiled = self.precompiled.as_ref()?;
+        let templates = if speaker {
+            precompiled.speakers
+        } else {
+            precompiled.templates
+        };
+
+        templates.get(format!("{}/{id}", precompiled.file).as_str())
+    }
+
+    fn render(&self, text: &str, template: Option<&Template>) -> Result<String, Error> {
+        match template {
+            Some(template) => self.compiler.compile_template_with(template, &self.ctx),
+            None => self.compiler.parse_and_compile_with(text, &self.ctx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{character, document, verbs};
+
+    type Res = Result<(), crate::Error>;
+
+    fn gen_compiler() -> DialogMacroCompiler<'static> {
+        DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict())
+    }
+
+    #[test]
+    fn walk_branches() -> Res {
+        let compiler = gen_compiler();
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+
+        let mut runner = DialogRunner::new(&compiler, &document);
+
+        assert_eq!(runner.current_node(), Some("start"));
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Hunk".to_string()),
+                text: "Hey! Have you met Pidge?".to_string(),
+            })
+        );
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Choices(vec!["Who?".to_string(), "Bye.".to_string()])
+        );
+
+        // Advancing again does not skip the choice
+        assert!(matches!(runner.advance()?, DialogEvent::Choices(_)));
+        assert!(matches!(runner.choose(2), Err(Error::InvalidChoice)));
+
+        runner.choose(0)?;
+        assert_eq!(runner.current_node(), Some("intro"));
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Hunk".to_string()),
+                text: "They are our engineer.".to_string(),
+            })
+        );
+
+        // Jumps back to the start
+        assert!(matches!(runner.advance()?, DialogEvent::Line(_)));
+        assert_eq!(runner.current_node(), Some("start"));
+        assert!(matches!(runner.advance()?, DialogEvent::Choices(_)));
+
+        runner.choose(1)?;
+        assert_eq!(runner.advance()?, DialogEvent::End);
+        assert!(runner.is_finished());
+        assert!(matches!(runner.choose(0), Err(Error::InvalidChoice)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn runner_context() -> Res {
+        let compiler = gen_compiler();
+        let document = XyrDocument::parse(
+            r#"You found {"type":"Variable","data":"gold","mods":[]} coins.
+* Take {"type":"Variable","data":"gold","mods":[]} coins -> END"#,
+        )?;
+
+        let mut variables = std::collections::HashMap::new();
+        variables.insert("gold".to_string(), crate::Value::from(7));
+
+        let mut runner = DialogRunner::new(&compiler, &document)
+            .with_context(RenderContext::new().with_variables(&variables));
+
+        assert!(
+            matches!(runner.advance()?, DialogEvent::Line(line) if line.text == "You found 7 coins.")
+        );
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Choices(vec!["Take 7 coins".to_string()])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn precompiled_templates() -> Res {
+        let compiler = gen_compiler();
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+
+        // Templates that differ from the document show that the runner doesn't parse its text
+        let templates = HashMap::from([
+            ("chat/start.0", Template::parse("Hello!")?),
+            (
+                "chat/start.choice.0",
+                Template::parse(r#"Who is {"character_id":"pidge","type":"Name","mods":[]}?"#)?,
+            ),
+        ]);
+        let speakers = HashMap::from([("chat/start.0", Template::parse("Narrator")?)]);
+
+        let mut runner =
+            DialogRunner::new(&compiler, &document).with_templates("chat", &templates, &speakers);
+
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Line(RenderedLine {
+                speaker: Some("Narrator".to_string()),
+                text: "Hello!".to_string(),
+            })
+        );
+
+        // Texts without a template are parsed
+        assert_eq!(
+            runner.advance()?,
+            DialogEvent::Choices(vec!["Who is Pidge?".to_string(), "Bye.".to_string()])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fall_through() -> Res {
+        let compiler = gen_compiler();
+        let document = XyrDocument::parse("=== a ===\nOne\n=== b ===\nTwo")?;
+
+        let mut runner = DialogRunner::starting_at(&compiler, &document, "a")?;
+
+        assert!(matches!(runner.advance()?, DialogEvent::Line(line) if line.text == "One"));
+        assert!(matches!(runner.advance()?, DialogEvent::Line(line) if line.text == "Two"));
+        assert_eq!(runner.advance()?, DialogEvent::End);
+
+        assert!(matches!(
+            DialogRunner::starting_at(&compiler, &document, "c"),
+            Err(Error::UnknownNodeLabel)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use schemars::{JsonSchema, SchemaGenerator};
+
+use crate::{
+    character::{CharacterCast, GrammaticalCharacter, Pronouns, Title},
+    dialog_parser::DialogMacro,
+    format::FORMAT_VERSION,
+    verbs::{Dictionary, Verb},
+};
+
+/// JSON Schemas of the data files and macros, keyed by type name, e.g. `"CharacterCast"`.
+///
+/// The schemas follow the serde representation of each type, so editors can validate and
+/// complete cast and dictionary files, and tools can check macros before they reach a compiler.
+pub fn schemas() -> BTreeMap<&'static str, serde_json::Value> {
+    BTreeMap::from([
+        ("CharacterCast", file_schema::<CharacterCast>()),
+        ("GrammaticalCharacter", schema::<GrammaticalCharacter>()),
+        ("Pronouns", schema::<Pronouns>()),
+        ("Title", schema::<Title>()),
+        ("Dictionary", file_schema::<Dictionary>()),
+        ("Verb", schema::<Verb>()),
+        ("DialogMacro", macro_schema()),
+    ])
+}
+
+fn schema<T: JsonSchema>() -> serde_json::Value {
+    SchemaGenerator::default()
+        .into_root_schema_for::<T>()
+        .into()
+}
+
+/// The schema of a data file, with the `version` field that `to_json` adds. Files without it are
+/// of an older layout that the schema doesn't describe.
+fn file_schema<T: JsonSchema>() -> serde_json::Value {
+    let mut schema = schema::<T>();
+
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert(
+            "version".to_string(),
+            serde_json::json!({
+                "description": "The version of the file layout.",
+                "const": FORMAT_VERSION,
+            }),
+        );
+    }
+
+    schema
+}
+
+/// The schema of `DialogMacro`, including the `type_`, `_type` and `mod` aliases its
+/// deserializer accepts.
+fn macro_schema() -> serde_json::Value {
+    let mut schema = schema::<DialogMacro>();
+
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        for (key, alias) in [("type", "type_"), ("type", "_type"), ("mods", "mod")] {
+            if let Some(property) = properties.get(key).cloned() {
+                properties.insert(alias.to_string(), property);
+            }
+        }
+    }
+
+    // Exactly one spelling of the type, like the deserializer
+    if let Some(schema) = schema.as_object_mut() {
+        schema.remove("required");
+        schema.insert(
+            "oneOf".to_string(),
+            serde_json::json!([
+                {"required": ["type"]},
+                {"required": ["type_"]},
+                {"required": ["_type"]},
+            ]),
+        );
+    }
+
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{character, document::XyrDocument, gettext::Message, verbs};
+
+    type Res = Result<(), crate::Error>;
+
+    fn validator(name: &str) -> jsonschema::Validator {
+        jsonschema::validator_for(&schemas()[name]).unwrap()
+    }
+
+    fn asset(name: &str) -> String {
+        std::fs::read_to_string(format!(
+            "{}/examples/assets/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn assets_test() -> Res {
+        let characters: serde_json::Value = serde_json::from_str(&asset("characters.json"))?;
+        assert!(validator("CharacterCast").is_valid(&characters));
+
+        let dictionary: serde_json::Value = serde_json::from_str(&asset("dictionary.json"))?;
+        assert!(validator("Dictionary").is_valid(&dictionary));
+
+        let macros = validator("DialogMacro");
+        let document = XyrDocument::parse(&asset("conversation.xyr"))?;
+
+        for (id, text) in document.texts() {
+            for macro_str in Message::new(id, text)?.macros {
+                assert!(macros.is_valid(&serde_json::from_str(&macro_str)?));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn serialized_test() -> Res {
+        let cast = validator("CharacterCast");
+        assert!(cast.is_valid(&serde_json::to_value(character::tests::gen_cast())?));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&character::tests::gen_cast().to_json()?)?;
+        assert!(cast.is_valid(&json));
+        assert!(!cast.is_valid(&serde_json::json!({"version": 2, "characters": {}})));
+
+        let dictionary = validator("Dictionary");
+        assert!(dictionary.is_valid(&serde_json::to_value(verbs::tests::gen_dict())?));
+
+        let macros = validator("DialogMacro");
+        assert!(macros.is_valid(&serde_json::json!({
+            "character_id": "pidge",
+            "type": "Shout",
+            "data": null,
+            "mods": ["Capitalized", {"Truncate": 3}, "Sparkle"],
+            "case": "Dative",
+        })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_test() {
+        let pronouns = validator("Pronouns");
+        assert!(pronouns.is_valid(&serde_json::json!("XeXyr")));
+        assert!(!pronouns.is_valid(&serde_json::json!({"Perspective": "FirstPlural"})));
+        assert!(!pronouns.is_valid(&serde_json::json!("HimHe")));
+
+        let macros = validator("DialogMacro");
+        assert!(!macros.is_valid(&serde_json::json!({"character_id": "pidge", "mods": []})));
+        assert!(!macros.is_valid(&serde_json::json!({
+            "character_id": "pidge",
+            "type": "Name",
+            "data": null,
+            "mods": [{"Truncate": -1}],
+        })));
+
+        assert!(macros.is_valid(&serde_json::json!({"type_": "Name", "mod": ["Capitalized"]})));
+        assert!(macros.is_valid(&serde_json::json!({"_type": "Name"})));
+        assert!(!macros.is_valid(&serde_json::json!({"type": "Name", "type_": "Name"})));
+        assert!(!macros.is_valid(&serde_json::json!({"type": "Name", "_type": "Name"})));
+        assert!(!macros.is_valid(&serde_json::json!({"type": "Name", "typ": "Name"})));
+
+        let verb = validator("Verb");
+        assert!(!verb.is_valid(&serde_json::json!({"infinitive": "be"})));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    character::Pronouns,
+    context::RenderContext,
+    dialog_parser::{DialogMacro, DialogMacroCompiler},
+    document::{TextId, XyrDocument},
+    gettext::Message,
+    Error,
+};
+
+const ID: &str = "id";
+const SPEAKER: &str = "speaker";
+const TEMPLATE: &str = "template";
+const MACROS: &str = "macros";
+
+/// Writes every line and choice of the document as CSV, for writers who review dialog in a
+/// spreadsheet.
+///
+/// The columns are the text ID, the rendered speaker, the template with its macros replaced by
+/// numbered placeholders like `{0}`, the macros behind the placeholders (one per line), and a
+/// rendered preview for each of the pronoun sets, in which every character named in the text
+/// uses that set. Speakers and previews are rendered with the given context, which provides the
+/// variables and groups the dialog uses.
+pub fn export_csv(
+    document: &XyrDocument,
+    compiler: &DialogMacroCompiler,
+    ctx: &RenderContext,
+    previews: &[Pronouns],
+) -> Result<String, Error> {
+    let mut header = vec![
+        ID.to_string(),
+        SPEAKER.to_string(),
+        TEMPLATE.to_string(),
+        MACROS.to_string(),
+    ];
+    header.extend(previews.iter().map(preview_label));
+
+    let mut csv = String::new();
+    write_record(&mut csv, &header);
+
+    for (id, text) in document.texts() {
+        let speaker = match &id {
+            TextId::Line { node, index } => document
+                .node(node)
+                .and_then(|node| node.lines()[*index].speaker()),
+            TextId::Choice { .. } => None,
+        };
+
+        let speaker = match speaker {
+            Some(speaker) => compiler.parse_and_compile_with(speaker, ctx)?,
+            None => String::new(),
+        };
+
+        let message = Message::new(id, text)?;
+
+        let mut record = vec![
+            message.id.to_string(),
+            speaker,
+            message.msgid.clone(),
+            message.macros.join("\n"),
+        ];
+
+        for pronouns in previews {
+            let mut ctx = ctx.clone();
+
+            for macro_str in &message.macros {
+                let macr: DialogMacro = serde_json::from_str(macro_str)?;
+
+                if let Some(character_id) = macr.character_id() {
+                    ctx = ctx.with_pronouns(character_id.to_string(), pronouns.clone());
+                }
+            }
+
+            record.push(compiler.parse_and_compile_with(text, &ctx)?);
+        }
+
+        write_record(&mut csv, &record);
+    }
+
+    Ok(csv)
+}
+
+/// Reads a CSV file written by [`export_csv`] back into a copy of the document.
+///
+/// Only the `id`, `template` and `macros` columns are read, in any order, so previews and extra
+/// columns can be edited freely. Every macro is checked against the compiler's cast, dictionary,
+/// macro types and mods, and a template has to use all of its macros.
+pub fn import_csv(
+    src: &str,
+    document: &XyrDocument,
+    compiler: &DialogMacroCompiler,
+) -> Result<XyrDocument, Error> {
+    let mut records = parse_csv(src)?.into_iter();
+
+    let header = records.next().ok_or(Error::MalformedCsv)?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column == name)
+            .ok_or(Error::MalformedCsv)
+    };
+
+    let (id, template, macros) = (column(ID)?, column(TEMPLATE)?, column(MACROS)?);
+
+    let mut imported = document.clone();
+
+    for record in records {
+        let field = |index: usize| record.get(index).map(String::as_str).unwrap_or_default();
+
+        // Empty rows are common at the end of exported spreadsheets
+        if record.iter().all(String::is_empty) {
+            continue;
+        }
+
+        let macros = field(macros)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|macro_str| {
+                compiler.check(&serde_json::from_str::<DialogMacro>(macro_str)?)?;
+                Ok(macro_str.to_string())
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let message = Message {
+            id: field(id).parse()?,
+            msgid: String::new(),
+            macros,
+        };
+
+        let text = message.restore(field(template))?;
+        imported.set_text(&message.id, text)?;
+    }
+
+    Ok(imported)
+}
+
+fn preview_label(pronouns: &Pronouns) -> String {
+    match pronouns {
+        Pronouns::HeHim => "he/him".to_string(),
+        Pronouns::SheHer => "she/her".to_string(),
+        Pronouns::ItIts => "it/its".to_string(),
+        Pronouns::TheyThem => "they/them".to_string(),
+        Pronouns::Name => "name".to_string(),
+        Pronouns::XeXyr => "xe/xyr".to_string(),
+        Pronouns::Custom {
+            subjective,
+            objective,
+            ..
+        } => format!("{subjective}/{objective}"),
+    }
+}
+
+fn write_record(csv: &mut String, record: &[String]) {
+    let fields: Vec<String> = record.iter().map(|field| quote(field)).collect();
+
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Reads the records of a CSV file as described in RFC 4180, accepting `\n` and `\r\n` line
+/// endings.
+fn parse_csv(src: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, '"') => return Err(Error::MalformedCsv),
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(Error::MalformedCsv);
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{character, document, verbs};
+
+    type Res = Result<(), crate::Error>;
+
+    fn gen_compiler() -> DialogMacroCompiler<'static> {
+        DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict())
+    }
+
+    #[test]
+    fn export_test() -> Res {
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+        let csv = export_csv(
+            &document,
+            &gen_compiler(),
+            &RenderContext::new(),
+            &[Pronouns::SheHer, Pronouns::TheyThem],
+        )?;
+
+        let records = parse_csv(&csv)?;
+
+        assert_eq!(
+            records[0],
+            [
+                "id",
+                "speaker",
+                "template",
+                "macros",
+                "she/her",
+                "they/them"
+            ]
+        );
+        assert_eq!(records[2][..3], ["start.choice.0", "", "Who?"]);
+
+        let intro = &records[4];
+        assert_eq!(intro[..3], ["intro.0", "Hunk", "{0} {1} our engineer."]);
+        assert_eq!(intro[3].lines().count(), 2);
+        assert_eq!(intro[4], "She is our engineer.");
+        assert_eq!(intro[5], "They are our engineer.");
+
+        // Variables come from the context
+        let document = XyrDocument::parse(
+            r#"You found {"type":"Variable","data":"gold"} {"type":"CountedNoun","data":"coin","count":"gold"}."#,
+        )?;
+        let mut variables = std::collections::HashMap::new();
+        variables.insert("gold".to_string(), crate::Value::from(7));
+        let ctx = RenderContext::new().with_variables(&variables);
+
+        let csv = export_csv(&document, &gen_compiler(), &ctx, &[Pronouns::HeHim])?;
+        assert_eq!(parse_csv(&csv)?[1][4], "You found 7 coins.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_test() -> Res {
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+        let compiler = gen_compiler();
+        let csv = export_csv(&document, &compiler, &RenderContext::new(), &[])?;
+
+        // Unchanged exports import to the same document
+        let imported = import_csv(&csv, &document, &compiler)?;
+        assert_eq!(imported.texts(), document.texts());
+
+        let csv = csv.replace("Hey! Have you met {0}?", "Hey {0}! Have you met {0}?");
+        let pidge = r#"{"character_id":"pidge","type":"Name","data":null,"mods":[]}"#;
+        let csv = csv.replace(
+            ",Who?,",
+            &format!(",{},{}", quote("Who is \"{0}\"?"), quote(pidge)),
+        );
+
+        let imported = import_csv(&csv, &document, &compiler)?;
+
+        assert_eq!(
+            compiler.parse_and_compile(imported.text(&"start.0".parse()?).unwrap())?,
+            "Hey Pidge! Have you met Pidge?"
+        );
+        assert_eq!(
+            compiler.parse_and_compile(imported.text(&"start.choice.0".parse()?).unwrap())?,
+            "Who is \"Pidge\"?"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_errors() -> Res {
+        let document = XyrDocument::parse(document::tests::BRANCHING)?;
+        let compiler = gen_compiler();
+
+        assert!(matches!(
+            import_csv("id,template\r\nstart.0,Hi", &document, &compiler),
+            Err(Error::MalformedCsv)
+        ));
+        assert!(matches!(
+            import_csv("id,template,macros\r\nstart.0,\"Hi", &document, &compiler),
+            Err(Error::MalformedCsv)
+        ));
+        assert!(matches!(
+            import_csv(
+                "id,template,macros\r\nstart.0,Hi {0},",
+                &document,
+                &compiler
+            ),
+            Err(Error::UnknownPlaceholder)
+        ));
+        assert!(matches!(
+            import_csv(
+                r#"id,template,macros
+start.0,Hi,"{""mods"":[]}""#,
+                &document,
+                &compiler
+            ),
+            Err(Error::Serde(_))
+        ));
+        assert!(matches!(
+            import_csv("id,template,macros\r\nstart.7,Hi,", &document, &compiler),
+            Err(Error::UnknownTextId)
+        ));
+        assert!(matches!(
+            import_csv(
+                r#"id,template,macros
+start.0,Hi {0},"{""character_id"":""pigde"",""type"":""Name""}""#,
+                &document,
+                &compiler
+            ),
+            Err(Error::UnknownCharacterIdentifier(_))
+        ));
+
+        Ok(())
+    }
+}
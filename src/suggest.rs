@@ -0,0 +1,58 @@
+/// Finds the candidate closest to a misspelled word, for "did you mean" hints in error messages.
+///
+/// Candidates further away than half the length of the word are no useful hint and ignored. Ties
+/// go to the alphabetically first candidate, so hints don't depend on the order of a map.
+pub(crate) fn closest<'c>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<&'c str> {
+    let limit = word.chars().count().div_ceil(2);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two words, ignoring case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().flat_map(char::to_lowercase).collect();
+    let b: Vec<char> = b.chars().flat_map(char::to_lowercase).collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_test() {
+        assert_eq!(edit_distance("pigde", "pidge"), 2);
+        assert_eq!(edit_distance("Name", "name"), 0);
+        assert_eq!(edit_distance("", "mods"), 4);
+
+        assert_eq!(closest("pigde", ["hunk", "pidge", "alfons"]), Some("pidge"));
+        assert_eq!(closest("to bee", ["to be", "to have"]), Some("to be"));
+        assert_eq!(closest("typ", ["type", "data"]), Some("type"));
+        assert_eq!(closest("xyz", ["type", "data", "mods"]), None);
+        assert_eq!(closest("bat", ["cat", "hat", "rat"]), Some("cat"));
+        assert_eq!(closest("bat", ["rat", "hat", "cat"]), Some("cat"));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    dialog_parser::{delimit_macro, DialogMacro},
+    Error,
+};
+
+/// XYR text with its macros parsed ahead of time, so compiling it doesn't need to parse JSON.
+///
+/// `DialogMacroCompiler::parse_and_compile` parses the text into a template on every call.
+/// Templates parsed once, or generated at build time by the `xyr!` macro, can be compiled any
+/// number of times with `DialogMacroCompiler::compile_template`.
+#[derive(Debug, Clone, Default)]
+pub struct Template<'a> {
+    segments: Vec<Segment<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Segment<'a> {
+    /// Plain text, with `{{` and `}}` already unescaped.
+    Text(&'a str),
+    /// A macro and the JSON it was parsed from, which `Fallback::RawMacro` renders.
+    Macro {
+        macr: DialogMacro<'a>,
+        source: &'a str,
+    },
+}
+
+impl<'a> Template<'a> {
+    pub fn new(segments: Vec<Segment<'a>>) -> Self {
+        Self { segments }
+    }
+
+    pub fn parse(src: &'a str) -> Result<Self, Error> {
+        let mut segments = Vec::new();
+        let mut rest = src;
+
+        while let Some(index) = rest.find(['{', '}']) {
+            let (text, from) = rest.split_at(index);
+
+            if !text.is_empty() {
+                segments.push(Segment::Text(text));
+            }
+
+            if from.starts_with("{{") || from.starts_with("}}") {
+                segments.push(Segment::Text(&from[..1]));
+                rest = &from[2..];
+            } else if from.starts_with('}') {
+                return Err(Error::UnmatchedClosingBrace);
+            } else {
+                let source = delimit_macro(from);
+
+                segments.push(Segment::Macro {
+                    macr: serde_json::from_str(source)?,
+                    source,
+                });
+                rest = &from[source.len()..];
+            }
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest));
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn segments(&self) -> &[Segment<'a>] {
+        &self.segments
+    }
+
+    /// The macros of the template, in order.
+    pub fn macros(&self) -> impl Iterator<Item = &DialogMacro<'a>> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Text(_) => None,
+            Segment::Macro { macr, .. } => Some(macr),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{character, verbs, DialogMacroCompiler};
+
+    type Res = Result<(), crate::Error>;
+
+    #[test]
+    fn parse_test() -> Res {
+        let template = Template::parse(
+            r#"{{Hi}} {"character_id":"pidge","type":"Name","data":null,"mods":[]}!"#,
+        )?;
+
+        let texts: Vec<&str> = template
+            .segments()
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => *text,
+                Segment::Macro { source, .. } => *source,
+            })
+            .collect();
+
+        assert_eq!(
+            texts,
+            [
+                "{",
+                "Hi",
+                "}",
+                " ",
+                r#"{"character_id":"pidge","type":"Name","data":null,"mods":[]}"#,
+                "!"
+            ]
+        );
+        assert_eq!(template.macros().count(), 1);
+
+        assert!(Template::parse("")?.segments().is_empty());
+        assert!(matches!(
+            Template::parse("Hi} there"),
+            Err(Error::UnmatchedClosingBrace)
+        ));
+        assert!(matches!(
+            Template::parse(r#"{"type":"Name""#),
+            Err(Error::Serde(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn compile_template_test() -> Res {
+        let compiler =
+            DialogMacroCompiler::new(character::tests::gen_cast(), verbs::tests::gen_dict())
+                .with_auto_capitalization(true);
+
+        let src = r#"{"character_id":"pidge","type":"SubjectivePronoun"} found {"type":"IndefiniteArticle"} "old" {{map}}."#;
+        let template = Template::parse(src)?;
+
+        assert_eq!(
+            compiler.compile_template(&template)?,
+            r#"They found an "old" {map}."#
+        );
+        assert_eq!(
+            compiler.compile_template(&template)?,
+            compiler.parse_and_compile(src)?
+        );
+
+        Ok(())
+    }
+}
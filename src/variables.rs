@@ -0,0 +1,116 @@
+use std::{collections::HashMap, fmt::Display, hash::BuildHasher};
+
+use serde::{Deserialize, Serialize};
+
+/// A game state value that can be interpolated into dialog.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Value {
+    /// The numeric value, if this is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            Value::Text(_) => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(value) => Display::fmt(value, f),
+            Value::Float(value) => Display::fmt(value, f),
+            Value::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+/// A source of variables that are looked up by name at render time.
+///
+/// Implement this for your game state to let dialog read from it directly.
+pub trait Variables {
+    fn get(&self, name: &str) -> Option<Value>;
+}
+
+impl<S: BuildHasher> Variables for HashMap<String, Value, S> {
+    fn get(&self, name: &str) -> Option<Value> {
+        HashMap::get(self, name).cloned()
+    }
+}
+
+impl<S: BuildHasher> Variables for HashMap<String, String, S> {
+    fn get(&self, name: &str) -> Option<Value> {
+        HashMap::get(self, name).map(|value| Value::Text(value.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Res = Result<(), crate::Error>;
+
+    #[test]
+    fn value_test() -> Res {
+        assert_eq!(Value::from(3).to_string(), "3");
+        assert_eq!(Value::from(2.5).to_string(), "2.5");
+        assert_eq!(Value::from("Bayard").to_string(), "Bayard");
+
+        assert_eq!(Value::from(3).as_f64(), Some(3.0));
+        assert_eq!(Value::from("3").as_f64(), None);
+
+        let values: HashMap<String, Value> = serde_json::from_str(r#"{"gold":12,"item":"lion"}"#)?;
+
+        assert_eq!(Variables::get(&values, "gold"), Some(Value::Integer(12)));
+        assert_eq!(
+            Variables::get(&values, "item"),
+            Some(Value::Text("lion".to_string()))
+        );
+        assert_eq!(Variables::get(&values, "quest"), None);
+
+        Ok(())
+    }
+}
@@ -1,17 +1,38 @@
 use std::{collections::HashMap, fmt::Display};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::Error;
+use crate::{format, Error, UnknownName};
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Conjugations of verbs, borrowed from the dictionary file. Files of any format version can be
+/// deserialized.
+#[derive(Debug, Default, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Dictionary<'a> {
-    #[serde(borrow)]
+    #[serde(rename = "verbs")]
     map: HashMap<String, Verb<'a>>,
 }
 
+impl<'de: 'a, 'a> Deserialize<'de> for Dictionary<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// The current layout, which also reads the `"map"` of version 0.
+        #[derive(Deserialize)]
+        struct Layout<'a> {
+            #[serde(default, rename = "version")]
+            _version: format::Version,
+            #[serde(borrow, alias = "map")]
+            verbs: HashMap<String, Verb<'a>>,
+        }
+
+        Ok(Self {
+            map: Layout::deserialize(deserializer)?.verbs,
+        })
+    }
+}
+
 // TODO: find a better name for this
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ConjugatePerson {
     FirstSingular,
     SecondSingular,
@@ -22,17 +43,30 @@ pub enum ConjugatePerson {
 }
 
 impl<'a> Dictionary<'a> {
-    pub fn insert(&mut self, key: String, verb: Verb<'a>) -> Option<Verb> {
+    /// Reads a dictionary file of any format version.
+    pub fn from_json(src: &'a str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(src)?)
+    }
+
+    /// Writes the dictionary in the current format version.
+    pub fn to_json(&self) -> Result<String, Error> {
+        format::to_json(self)
+    }
+
+    pub fn insert(&mut self, key: String, verb: Verb<'a>) -> Option<Verb<'a>> {
         self.map.insert(key, verb)
     }
 
-    pub fn remove(&mut self, key: &str) -> Option<Verb> {
+    pub fn remove(&mut self, key: &str) -> Option<Verb<'a>> {
         self.map.remove(key)
     }
 
     pub fn conjugate(&self, key: &str, person: ConjugatePerson) -> Result<String, Error> {
         let Some(verb) = self.map.get(key) else {
-            return Err(Error::UnknownVerbKey);
+            return Err(Error::UnknownVerbKey(UnknownName::new(
+                key,
+                self.map.keys().map(String::as_str),
+            )));
         };
 
         let Some(conj_verb) = verb.get(person) else {
@@ -41,9 +75,20 @@ impl<'a> Dictionary<'a> {
 
         Ok(conj_verb.to_string())
     }
+
+    /// The keys of all verbs.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    /// The infinitive of the verb, if the dictionary has it.
+    pub fn infinitive(&self, key: &str) -> Option<&'a str> {
+        self.map.get(key)?.infinitive
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Verb<'a> {
     debug_ident: &'a str,
     infinitive: Option<&'a str>,